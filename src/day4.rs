@@ -76,8 +76,11 @@
 //! To guarantee victory against the giant squid, figure out which board will
 //! win first. What will your final score be if you choose that board?
 
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
+use crate::utils::SplitMix64;
+
 struct BingoCard {
     nums: [[u8; 5]; 5],
     marks: [[bool; 5]; 5],
@@ -190,6 +193,799 @@ fn sort_into_winners(nums: Vec<u8>, cards: Vec<BingoCard>) -> Vec<(BingoCard, u8
         .1
 }
 
+/// Number of cells on each side of a bingo card.
+const CARD_SIZE: usize = 5;
+
+/// A bingo engine that marks many cards at once without scanning them.
+///
+/// On construction, every number on every card is indexed so that a draw only
+/// visits the cells holding that number.  Each card keeps a counter of the
+/// unmarked cells remaining in every row and column, so a card is known to win
+/// the moment one of those counters reaches zero, without re-checking the
+/// whole board.  Each draw is therefore O(occurrences of the drawn number).
+struct BingoEngine {
+    /// Maps each number to the (card, cell) pairs on which it appears, where
+    /// the cell is `row * CARD_SIZE + col`.
+    index: Vec<Vec<(usize, u8)>>,
+    /// Bitmask of the marked cells of each card.
+    marked: Vec<u32>,
+    /// Unmarked cells remaining in each row of each card.
+    rows_remaining: Vec<[u8; CARD_SIZE]>,
+    /// Unmarked cells remaining in each column of each card.
+    cols_remaining: Vec<[u8; CARD_SIZE]>,
+    /// Sum of the unmarked numbers of each card.
+    unmarked_sums: Vec<u32>,
+    /// The number that made each card a winner, if it has won.
+    winning_numbers: Vec<Option<u8>>,
+    /// Cards that arrived with a row or column already marked, which are
+    /// reported as winners on the next draw, as [`sort_into_winners`] does.
+    pending_wins: Vec<usize>,
+    /// The number of draws made so far.
+    draws: usize,
+}
+
+impl BingoEngine {
+    fn new(cards: &[BingoCard]) -> Self {
+        let mut index = vec![Vec::new(); u8::MAX as usize + 1];
+        let mut marked = Vec::with_capacity(cards.len());
+        let mut unmarked_sums = Vec::with_capacity(cards.len());
+
+        for (card_idx, card) in cards.iter().enumerate() {
+            let mut mask = 0;
+            let mut sum = 0;
+            for (i, row) in card.nums.iter().enumerate() {
+                for (j, num) in row.iter().enumerate() {
+                    let cell = i * CARD_SIZE + j;
+                    if card.marks[i][j] {
+                        mask |= 1 << cell;
+                    } else {
+                        sum += *num as u32;
+                        index[*num as usize].push((card_idx, cell as u8));
+                    }
+                }
+            }
+            marked.push(mask);
+            unmarked_sums.push(sum);
+        }
+
+        let mut engine = Self {
+            index,
+            marked,
+            rows_remaining: Vec::with_capacity(cards.len()),
+            cols_remaining: Vec::with_capacity(cards.len()),
+            unmarked_sums,
+            winning_numbers: vec![None; cards.len()],
+            pending_wins: Vec::new(),
+            draws: 0,
+        };
+
+        // cards may arrive partially marked, so derive the line counters from
+        // the marks rather than assuming full lines.
+        for card_idx in 0..cards.len() {
+            let mask = engine.marked[card_idx];
+            let mut rows = [CARD_SIZE as u8; CARD_SIZE];
+            let mut cols = [CARD_SIZE as u8; CARD_SIZE];
+            for cell in (0..CARD_SIZE * CARD_SIZE).filter(|cell| mask & (1 << cell) != 0) {
+                rows[cell / CARD_SIZE] -= 1;
+                cols[cell % CARD_SIZE] -= 1;
+            }
+            if rows.contains(&0) || cols.contains(&0) {
+                engine.pending_wins.push(card_idx);
+            }
+            engine.rows_remaining.push(rows);
+            engine.cols_remaining.push(cols);
+        }
+
+        engine
+    }
+
+    /// The number of cards in the engine.
+    fn len(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Marks `num` on every card, and returns the indices of the cards that
+    /// became winners because of it, in ascending order.  Cards that have
+    /// already won continue to be marked, but are not reported again.
+    fn draw(&mut self, num: u8) -> Vec<usize> {
+        let mut new_winners = Vec::new();
+        self.draw_with(num, |event| {
+            if let BingoEvent::Win { card, .. } = event {
+                new_winners.push(card);
+            }
+        });
+
+        new_winners.sort_unstable();
+        new_winners
+    }
+
+    /// Marks `num` on every card, reporting everything that happens to
+    /// `on_event`: the draw itself, followed by each cell marked, each row or
+    /// column completed, and each card that wins as a result.
+    fn draw_with(&mut self, num: u8, mut on_event: impl FnMut(BingoEvent)) {
+        on_event(BingoEvent::Draw {
+            turn: self.draws,
+            num,
+        });
+        self.draws += 1;
+
+        for &(card_idx, cell) in &self.index[num as usize] {
+            let bit = 1 << cell;
+            if self.marked[card_idx] & bit != 0 {
+                continue;
+            }
+
+            self.marked[card_idx] |= bit;
+            self.unmarked_sums[card_idx] -= num as u32;
+
+            let (row, col) = (cell as usize / CARD_SIZE, cell as usize % CARD_SIZE);
+            on_event(BingoEvent::Mark {
+                card: card_idx,
+                row,
+                col,
+            });
+
+            self.rows_remaining[card_idx][row] -= 1;
+            self.cols_remaining[card_idx][col] -= 1;
+
+            let mut completes_line = false;
+            if self.rows_remaining[card_idx][row] == 0 {
+                completes_line = true;
+                on_event(BingoEvent::Complete {
+                    card: card_idx,
+                    line: BingoLine::Row(row),
+                });
+            }
+            if self.cols_remaining[card_idx][col] == 0 {
+                completes_line = true;
+                on_event(BingoEvent::Complete {
+                    card: card_idx,
+                    line: BingoLine::Col(col),
+                });
+            }
+
+            if completes_line && self.winning_numbers[card_idx].is_none() {
+                self.winning_numbers[card_idx] = Some(num);
+                on_event(BingoEvent::Win {
+                    card: card_idx,
+                    score: num as u32 * self.unmarked_sums[card_idx],
+                });
+            }
+        }
+
+        for card_idx in std::mem::take(&mut self.pending_wins) {
+            if self.winning_numbers[card_idx].is_none() {
+                self.winning_numbers[card_idx] = Some(num);
+                on_event(BingoEvent::Win {
+                    card: card_idx,
+                    score: num as u32 * self.unmarked_sums[card_idx],
+                });
+            }
+        }
+    }
+
+    /// Whether the given cell of the given card is marked.
+    fn is_marked(&self, card_idx: usize, row: usize, col: usize) -> bool {
+        self.marked[card_idx] & (1 << (row * CARD_SIZE + col)) != 0
+    }
+
+    /// The number that made the given card a winner, if it has won.
+    fn winning_number(&self, card_idx: usize) -> Option<u8> {
+        self.winning_numbers[card_idx]
+    }
+
+    /// Sum of the numbers on the given card that have not been marked.
+    fn sum_unmarked(&self, card_idx: usize) -> u32 {
+        self.unmarked_sums[card_idx]
+    }
+
+    /// The score of the given card: the sum of its unmarked numbers times the
+    /// number that made it a winner, or `None` if it has not won.
+    fn score(&self, card_idx: usize) -> Option<u32> {
+        self.winning_number(card_idx)
+            .map(|num| num as u32 * self.sum_unmarked(card_idx))
+    }
+}
+
+/// Indexed equivalent of [`sort_into_winners`].  Plays every number in `nums`
+/// and returns `(card index, winning number, score)` for each card in the
+/// order in which they win.  The score is taken at the moment the card wins,
+/// so later draws do not affect it.
+fn sort_into_winners_indexed(nums: &[u8], cards: &[BingoCard]) -> Vec<(usize, u8, u32)> {
+    let mut engine = BingoEngine::new(cards);
+    let mut winners = Vec::new();
+    for &num in nums {
+        if winners.len() == engine.len() {
+            break;
+        }
+
+        for card_idx in engine.draw(num) {
+            winners.push((card_idx, num, engine.score(card_idx).unwrap()));
+        }
+    }
+
+    winners
+}
+
+/// A row or column of a bingo card.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BingoLine {
+    Row(usize),
+    Col(usize),
+}
+
+/// Something that happened during a game of bingo.
+///
+/// Events serialize to a single line of text, e.g. `draw 11 24`, `mark 2 0 3`,
+/// `complete 2 row 0` or `win 2 4512`, and parse back with [`FromStr`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BingoEvent {
+    /// The `turn`th number drawn (counting from zero) was `num`.
+    Draw { turn: usize, num: u8 },
+    /// The cell at (`row`, `col`) of `card` was marked.
+    Mark { card: usize, row: usize, col: usize },
+    /// Every cell of `line` on `card` is now marked.
+    Complete { card: usize, line: BingoLine },
+    /// `card` won with the given score.
+    Win { card: usize, score: u32 },
+}
+
+impl Display for BingoEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BingoEvent::Draw { turn, num } => write!(f, "draw {} {}", turn, num),
+            BingoEvent::Mark { card, row, col } => write!(f, "mark {} {} {}", card, row, col),
+            BingoEvent::Complete {
+                card,
+                line: BingoLine::Row(row),
+            } => write!(f, "complete {} row {}", card, row),
+            BingoEvent::Complete {
+                card,
+                line: BingoLine::Col(col),
+            } => write!(f, "complete {} col {}", card, col),
+            BingoEvent::Win { card, score } => write!(f, "win {} {}", card, score),
+        }
+    }
+}
+
+/// Error returned when a serialized [`BingoEvent`] cannot be parsed.
+#[derive(Debug, PartialEq, Eq)]
+struct ParseBingoEventError {
+    /// The offending text.
+    text: String,
+}
+
+impl Display for ParseBingoEventError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid bingo event: {:?}", self.text)
+    }
+}
+
+impl FromStr for BingoEvent {
+    type Err = ParseBingoEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse_toks(toks: &[&str]) -> Option<BingoEvent> {
+            let event = match toks {
+                ["draw", turn, num] => BingoEvent::Draw {
+                    turn: turn.parse().ok()?,
+                    num: num.parse().ok()?,
+                },
+                ["mark", card, row, col] => BingoEvent::Mark {
+                    card: card.parse().ok()?,
+                    row: row.parse().ok()?,
+                    col: col.parse().ok()?,
+                },
+                ["complete", card, kind, idx] => {
+                    let idx = idx.parse().ok()?;
+                    let line = match *kind {
+                        "row" => BingoLine::Row(idx),
+                        "col" => BingoLine::Col(idx),
+                        _ => return None,
+                    };
+                    BingoEvent::Complete {
+                        card: card.parse().ok()?,
+                        line,
+                    }
+                }
+                ["win", card, score] => BingoEvent::Win {
+                    card: card.parse().ok()?,
+                    score: score.parse().ok()?,
+                },
+                _ => return None,
+            };
+            Some(event)
+        }
+
+        let toks = s.split_ascii_whitespace().collect::<Vec<_>>();
+        parse_toks(&toks).ok_or_else(|| ParseBingoEventError {
+            text: s.to_string(),
+        })
+    }
+}
+
+/// Plays every number in `nums` against `cards` and records everything that
+/// happens, in order.
+fn record_game(nums: &[u8], cards: &[BingoCard]) -> Vec<BingoEvent> {
+    let mut engine = BingoEngine::new(cards);
+    let mut events = Vec::new();
+    for &num in nums {
+        engine.draw_with(num, |e| events.push(e));
+    }
+    events
+}
+
+/// Serializes a game log, one event per line.
+fn write_events(events: &[BingoEvent]) -> String {
+    events.iter().map(|e| format!("{}\n", e)).collect()
+}
+
+/// Parses a game log written by [`write_events`].  Blank lines are ignored.
+fn parse_events(input: &str) -> Result<Vec<BingoEvent>, ParseBingoEventError> {
+    input
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(BingoEvent::from_str)
+        .collect()
+}
+
+/// How marked numbers are distinguished when rendering cards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Highlight {
+    /// Surround marked numbers with square brackets.
+    Brackets,
+    /// Render marked numbers in bold using ANSI escape codes.
+    Ansi,
+}
+
+/// Error returned when a recorded event does not fit the cards being replayed.
+#[derive(Debug, PartialEq, Eq)]
+struct ReplayError {
+    /// Index of the offending event in the log.
+    position: usize,
+    /// The offending event.
+    event: BingoEvent,
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "event {} ({}) does not match the cards",
+            self.position, self.event
+        )
+    }
+}
+
+/// Steps through a recorded game one event at a time, tracking which cells of
+/// each card have been marked so far.
+///
+/// The log only records what happened during the game, so the replay starts
+/// from whatever marks the given cards already carry; pass the cards as they
+/// were when the game was recorded.
+struct BingoReplay<'a> {
+    cards: &'a [BingoCard],
+    events: &'a [BingoEvent],
+    /// Index of the next event to apply.
+    position: usize,
+    /// Bitmask of the marked cells of each card.
+    marked: Vec<u32>,
+    /// The most recently drawn number.
+    last_drawn: Option<u8>,
+    /// The number of draws applied so far.
+    draws: usize,
+}
+
+impl<'a> BingoReplay<'a> {
+    fn new(cards: &'a [BingoCard], events: &'a [BingoEvent]) -> Self {
+        let marked = cards
+            .iter()
+            .map(|card| {
+                (0..CARD_SIZE * CARD_SIZE)
+                    .filter(|cell| card.marks[cell / CARD_SIZE][cell % CARD_SIZE])
+                    .fold(0, |mask, cell| mask | 1 << cell)
+            })
+            .collect();
+        Self {
+            cards,
+            events,
+            position: 0,
+            marked,
+            last_drawn: None,
+            draws: 0,
+        }
+    }
+
+    /// Applies the next event and returns it, or returns `None` at the end of
+    /// the log.  An event that cannot have happened (a draw out of turn, an
+    /// unknown card or cell, a mark of a marked cell or of one that does not
+    /// match the last draw, a line that is not complete, or a win without a
+    /// complete line or with the wrong score) is rejected without being
+    /// applied.
+    fn step(&mut self) -> Result<Option<BingoEvent>, ReplayError> {
+        let event = match self.events.get(self.position) {
+            Some(event) => *event,
+            None => return Ok(None),
+        };
+        let valid = match event {
+            BingoEvent::Draw { turn, .. } => turn == self.draws,
+            BingoEvent::Mark { card, row, col } => {
+                card < self.cards.len()
+                    && row < CARD_SIZE
+                    && col < CARD_SIZE
+                    && !self.is_marked(card, row, col)
+                    && self.last_drawn == Some(self.cards[card].nums[row][col])
+            }
+            BingoEvent::Complete { card, line } => {
+                card < self.cards.len() && self.is_complete(card, line)
+            }
+            BingoEvent::Win { card, score } => {
+                card < self.cards.len()
+                    && (0..CARD_SIZE).any(|i| {
+                        self.is_complete(card, BingoLine::Row(i))
+                            || self.is_complete(card, BingoLine::Col(i))
+                    })
+                    && Some(score) == self.score(card)
+            }
+        };
+        if !valid {
+            return Err(ReplayError {
+                position: self.position,
+                event,
+            });
+        }
+
+        self.position += 1;
+        match event {
+            BingoEvent::Draw { num, .. } => {
+                self.last_drawn = Some(num);
+                self.draws += 1;
+            }
+            BingoEvent::Mark { card, row, col } => {
+                self.marked[card] |= 1 << (row * CARD_SIZE + col);
+            }
+            BingoEvent::Complete { .. } | BingoEvent::Win { .. } => {}
+        }
+        Ok(Some(event))
+    }
+
+    /// Applies events up to, but not including, the next draw, so that the
+    /// replay is positioned at the end of the current draw.  Returns the
+    /// events that were applied, or the first event that could not be.
+    fn step_draw(&mut self) -> Result<Vec<BingoEvent>, ReplayError> {
+        let mut applied = Vec::new();
+        if let Some(event) = self.step()? {
+            applied.push(event);
+        }
+        while !matches!(
+            self.events.get(self.position),
+            None | Some(BingoEvent::Draw { .. })
+        ) {
+            applied.extend(self.step()?);
+        }
+        Ok(applied)
+    }
+
+    /// Whether every cell of `line` on `card_idx` has been marked.
+    fn is_complete(&self, card_idx: usize, line: BingoLine) -> bool {
+        match line {
+            BingoLine::Row(i) | BingoLine::Col(i) if i >= CARD_SIZE => false,
+            BingoLine::Row(row) => (0..CARD_SIZE).all(|col| self.is_marked(card_idx, row, col)),
+            BingoLine::Col(col) => (0..CARD_SIZE).all(|row| self.is_marked(card_idx, row, col)),
+        }
+    }
+
+    /// The score of `card_idx` if it won on the last drawn number.
+    fn score(&self, card_idx: usize) -> Option<u32> {
+        let unmarked = (0..CARD_SIZE * CARD_SIZE)
+            .filter(|cell| self.marked[card_idx] & (1 << cell) == 0)
+            .map(|cell| self.cards[card_idx].nums[cell / CARD_SIZE][cell % CARD_SIZE] as u32)
+            .sum::<u32>();
+        self.last_drawn.map(|num| unmarked * num as u32)
+    }
+
+    /// Whether the given cell of the given card has been marked so far.
+    fn is_marked(&self, card_idx: usize, row: usize, col: usize) -> bool {
+        self.marked[card_idx] & (1 << (row * CARD_SIZE + col)) != 0
+    }
+
+    /// Renders every card adjacent to each other, as in the puzzle text, with
+    /// the numbers marked so far highlighted.
+    fn render(&self, highlight: Highlight) -> String {
+        let mut out = String::new();
+        for row in 0..CARD_SIZE {
+            let rendered_rows = (0..self.cards.len())
+                .map(|card_idx| self.render_row(card_idx, row, highlight))
+                .collect::<Vec<_>>();
+            out.push_str(rendered_rows.join("        ").trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_row(&self, card_idx: usize, row: usize, highlight: Highlight) -> String {
+        (0..CARD_SIZE)
+            .map(|col| {
+                let num = self.cards[card_idx].nums[row][col];
+                match (self.is_marked(card_idx, row, col), highlight) {
+                    (false, Highlight::Brackets) => format!(" {:>2} ", num),
+                    (true, Highlight::Brackets) => format!("[{:>2}]", num),
+                    (false, Highlight::Ansi) => format!("{:>2}", num),
+                    (true, Highlight::Ansi) => format!("\x1b[1m{:>2}\x1b[0m", num),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(if highlight == Highlight::Ansi {
+                " "
+            } else {
+                ""
+            })
+    }
+}
+
+/// How draw orders are sampled when analyzing a set of cards.
+#[derive(Copy, Clone, Debug)]
+enum AnalysisMode {
+    /// Play `trials` uniformly random permutations of the pool.
+    MonteCarlo { trials: usize, seed: u64 },
+    /// Play every permutation of the pool.  Only practical for small pools.
+    Exhaustive,
+}
+
+/// The largest pool for which [`AnalysisMode::Exhaustive`] is allowed (10! is
+/// a little over 3.6 million games).
+const MAX_EXHAUSTIVE_POOL: usize = 10;
+
+/// Error returned when a set of cards cannot be analyzed.
+#[derive(Debug, PartialEq, Eq)]
+enum AnalysisError {
+    /// There are no cards to analyze.
+    NoCards,
+    /// [`AnalysisMode::MonteCarlo`] was asked for zero trials.
+    NoTrials,
+    /// The pool is larger than [`MAX_EXHAUSTIVE_POOL`] in
+    /// [`AnalysisMode::Exhaustive`].
+    PoolTooLarge(usize),
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::NoCards => write!(f, "no cards to analyze"),
+            AnalysisError::NoTrials => write!(f, "no trials to play"),
+            AnalysisError::PoolTooLarge(len) => {
+                write!(f, "pool of {} numbers is too large to enumerate", len)
+            }
+        }
+    }
+}
+
+/// Per-card outcomes of playing many draw orders.
+#[derive(Debug)]
+struct DrawOrderAnalysis {
+    /// Number of draw orders played.
+    games: usize,
+    /// Probability that each card is the first to win.  When several cards
+    /// win on the same draw, they share the credit equally.
+    first_win: Vec<f64>,
+    /// Probability that each card is the last to win, shared in the same way.
+    last_win: Vec<f64>,
+    /// Mean number of draws each card needs to win, over the games in which
+    /// it wins, or `None` if it never won.  A card that arrives with a
+    /// complete line needs none.
+    expected_draws: Vec<Option<f64>>,
+}
+
+impl DrawOrderAnalysis {
+    /// The card most likely to win first: the one to pick to beat the squid.
+    fn best_to_win_first(&self) -> usize {
+        argmax(&self.first_win)
+    }
+
+    /// The card most likely to win last: the one to pick to let the squid win.
+    fn best_to_win_last(&self) -> usize {
+        argmax(&self.last_win)
+    }
+}
+
+fn argmax(values: &[f64]) -> usize {
+    (0..values.len())
+        .max_by(|a, b| values[*a].total_cmp(&values[*b]))
+        .unwrap()
+}
+
+/// Plays `cards` against orderings of the numbers in `pool`, as chosen by
+/// `mode`, and reports how likely each card is to win first or last and how
+/// long each card takes to win.
+fn analyze_draw_orders(
+    cards: &[BingoCard],
+    pool: &[u8],
+    mode: AnalysisMode,
+) -> Result<DrawOrderAnalysis, AnalysisError> {
+    if cards.is_empty() {
+        return Err(AnalysisError::NoCards);
+    }
+    match mode {
+        AnalysisMode::MonteCarlo { trials: 0, .. } => return Err(AnalysisError::NoTrials),
+        AnalysisMode::Exhaustive if pool.len() > MAX_EXHAUSTIVE_POOL => {
+            return Err(AnalysisError::PoolTooLarge(pool.len()))
+        }
+        _ => {}
+    }
+
+    let mut first_win = vec![0.0; cards.len()];
+    let mut last_win = vec![0.0; cards.len()];
+    let mut total_draws = vec![0_u64; cards.len()];
+    let mut wins = vec![0_u64; cards.len()];
+    let mut games = 0;
+
+    let mut play = |order: &[u8]| {
+        games += 1;
+        let mut engine = BingoEngine::new(cards);
+        let mut win_turns = vec![None; cards.len()];
+
+        // cards that arrive with a complete line have won before any draw
+        for card_idx in &engine.pending_wins {
+            win_turns[*card_idx] = Some(0);
+        }
+        let mut num_winners = engine.pending_wins.len();
+        for (turn, num) in order.iter().enumerate() {
+            if num_winners == cards.len() {
+                break;
+            }
+            for card_idx in engine.draw(*num) {
+                if win_turns[card_idx].is_none() {
+                    win_turns[card_idx] = Some(turn + 1);
+                    num_winners += 1;
+                }
+            }
+        }
+
+        let first = win_turns.iter().flatten().min();
+        let last = win_turns.iter().flatten().max();
+        for (outcome, turn) in [(&mut first_win, first), (&mut last_win, last)] {
+            if let Some(turn) = turn {
+                let tied = win_turns.iter().filter(|t| **t == Some(*turn)).count();
+                for card_idx in 0..cards.len() {
+                    if win_turns[card_idx] == Some(*turn) {
+                        outcome[card_idx] += 1.0 / tied as f64;
+                    }
+                }
+            }
+        }
+        for (card_idx, turn) in win_turns.iter().enumerate() {
+            if let Some(turn) = turn {
+                wins[card_idx] += 1;
+                total_draws[card_idx] += *turn as u64;
+            }
+        }
+    };
+
+    let mut order = pool.to_vec();
+    match mode {
+        AnalysisMode::MonteCarlo { trials, seed } => {
+            let mut rng = SplitMix64::new(seed);
+            for _ in 0..trials {
+                rng.shuffle(&mut order);
+                play(&order);
+            }
+        }
+        AnalysisMode::Exhaustive => {
+            for_each_permutation(&mut order, &mut play);
+        }
+    }
+
+    Ok(DrawOrderAnalysis {
+        games,
+        first_win: first_win.iter().map(|w| w / games as f64).collect(),
+        last_win: last_win.iter().map(|w| w / games as f64).collect(),
+        expected_draws: wins
+            .iter()
+            .zip(total_draws.iter())
+            .map(|(w, t)| {
+                if *w == 0 {
+                    None
+                } else {
+                    Some(*t as f64 / *w as f64)
+                }
+            })
+            .collect(),
+    })
+}
+
+/// Calls `f` with every permutation of `values` (Heap's algorithm).
+fn for_each_permutation<T>(values: &mut [T], f: &mut impl FnMut(&[T])) {
+    fn heap<T>(k: usize, values: &mut [T], f: &mut impl FnMut(&[T])) {
+        if k <= 1 {
+            f(values);
+            return;
+        }
+        for i in 0..k - 1 {
+            heap(k - 1, values, f);
+            if k & 1 == 0 {
+                values.swap(i, k - 1);
+            } else {
+                values.swap(0, k - 1);
+            }
+        }
+        heap(k - 1, values, f);
+    }
+
+    heap(values.len(), values, f)
+}
+
+/// Computes exactly the expected number of draws `card` needs to win when the
+/// numbers in `pool` are drawn in a uniformly random order, or `None` if the
+/// card cannot win from `pool`.  Marked cells count as already drawn, so a card
+/// with a complete line needs no draws at all.
+///
+/// The card has won after `t` draws if the unmarked numbers of any of its rows
+/// or columns are contained in the first `t` numbers.  For a set of `s`
+/// numbers, that happens with probability C(n - s, t - s) / C(n, t), so the
+/// probability of the union of the ten lines follows from inclusion-exclusion
+/// over subsets of lines, and E[T] = sum over t of P(T > t).
+fn expected_draws_to_win(card: &BingoCard, pool: &[u8]) -> Option<f64> {
+    let mut in_pool = [false; 256];
+    pool.iter().for_each(|n| in_pool[*n as usize] = true);
+    let n = in_pool.iter().filter(|p| **p).count();
+
+    // each line as a bitset of the unmarked numbers it contains, keeping only
+    // the lines that can be completed from the pool.
+    let unmarked = |i: usize, j: usize| Some(card.nums[i][j]).filter(|_| !card.marks[i][j]);
+    let lines = (0..CARD_SIZE)
+        .map(|i| {
+            (0..CARD_SIZE)
+                .filter_map(|j| unmarked(i, j))
+                .collect::<Vec<_>>()
+        })
+        .chain((0..CARD_SIZE).map(|j| (0..CARD_SIZE).filter_map(|i| unmarked(i, j)).collect()))
+        .filter(|line| line.iter().all(|num| in_pool[*num as usize]))
+        .map(|line| {
+            let mut set = [0_u128; 2];
+            line.iter()
+                .for_each(|num| set[*num as usize / 128] |= 1 << (*num as usize % 128));
+            set
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+
+    // number of subsets of lines with each union size and parity
+    let mut signed_counts = vec![0_i64; n + 1];
+    for subset in 1_usize..(1 << lines.len()) {
+        let mut union = [0_u128; 2];
+        for (i, line) in lines.iter().enumerate() {
+            if subset & (1 << i) != 0 {
+                union[0] |= line[0];
+                union[1] |= line[1];
+            }
+        }
+        let size = (union[0].count_ones() + union[1].count_ones()) as usize;
+        signed_counts[size] += if subset.count_ones() % 2 == 1 { 1 } else { -1 };
+    }
+
+    // P(T > t) = 1 - P(won within t draws)
+    let expected = (0..n)
+        .map(|t| {
+            let won = (0..=t)
+                .filter(|s| signed_counts[*s] != 0)
+                .map(|s| signed_counts[s] as f64 * all_drawn_probability(n, s, t))
+                .sum::<f64>();
+            1.0 - won
+        })
+        .sum();
+    Some(expected)
+}
+
+/// Probability that `s` specific numbers are all among the first `t` drawn
+/// from a pool of `n`: C(n - s, t - s) / C(n, t), computed as a running product
+/// of t (t - 1) ... (t - s + 1) / (n (n - 1) ... (n - s + 1)).
+fn all_drawn_probability(n: usize, s: usize, t: usize) -> f64 {
+    (0..s).map(|i| (t - i) as f64 / (n - i) as f64).product()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,4 +1056,568 @@ mod tests {
             13158
         );
     }
+
+    #[test]
+    fn test_engine_board_3() {
+        let input_str = r"
+        7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+         14 21 17 24  4
+         10 16 15  9 19
+         18  8 23 26 20
+         22 11 13  6  5
+          2  0 12  3  7";
+
+        let (nums, cards) = parse_input(input_str);
+        let mut engine = BingoEngine::new(&cards);
+
+        let mut nums = nums.into_iter();
+        assert!(engine.draw(nums.next().unwrap()).is_empty());
+        assert!(engine.is_marked(0, 4, 4));
+        assert!(!engine.is_marked(0, 0, 0));
+
+        let mut last_called = 0;
+        while engine.winning_number(0).is_none() {
+            last_called = nums.next().unwrap();
+            engine.draw(last_called);
+        }
+
+        assert_eq!(last_called, 24);
+        assert_eq!(engine.sum_unmarked(0), 188);
+        assert_eq!(engine.score(0), Some(4512));
+    }
+
+    /// Drawing a number twice must not count its cells twice.
+    #[test]
+    fn test_engine_repeated_draw() {
+        let (_, cards) = parse_input(
+            r"
+            1
+            1  2  3  4  5
+            6  7  8  9 10
+           11 12 13 14 15
+           16 17 18 19 20
+           21 22 23 24 25",
+        );
+
+        let mut engine = BingoEngine::new(&cards);
+        for num in [1, 1, 1, 1, 1, 2, 3, 4] {
+            assert!(engine.draw(num).is_empty());
+        }
+        assert_eq!(engine.draw(5), vec![0]);
+        assert_eq!(engine.sum_unmarked(0), (6..=25).sum());
+    }
+
+    /// The indexed engine must agree with [`sort_into_winners`] on every card.
+    #[test]
+    fn test_engine_matches_sort_into_winners() {
+        let input = read_puzzle_input("day4-puzzle-input.txt");
+        let (nums, cards) = parse_input(&input);
+        let indexed = sort_into_winners_indexed(&nums, &cards);
+        let expected = sort_into_winners(nums, cards);
+
+        assert_eq!(indexed.len(), expected.len());
+        for ((_, num, score), (card, expected_num)) in indexed.iter().zip(expected.iter()) {
+            assert_eq!(num, expected_num);
+            assert_eq!(*score, *expected_num as u32 * card.sum_unmarked());
+        }
+        assert_eq!(indexed.first().unwrap().2, 54275);
+        assert_eq!(indexed.last().unwrap().2, 13158);
+    }
+
+    /// A card that arrives with a complete row wins on the first draw.
+    #[test]
+    fn test_engine_pre_won_card() {
+        let input = r"
+            6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25
+
+            1  2  3  4  5
+            6  7  8  9 10
+           11 12 13 14 15
+           16 17 18 19 20
+           21 22 23 24 25
+
+           26 27 28 29 30
+            6  7  8  9 10
+           31 32 33 34 35
+           36 37 38 39 40
+           41 42 43 44 45";
+        let (nums, mut cards) = parse_input(input);
+        for num in 1..=5 {
+            mark_cards(&mut cards, num);
+        }
+        let indexed = sort_into_winners_indexed(&nums, &cards);
+        assert_eq!(
+            indexed,
+            vec![
+                (0, 6, 6 * (7..=25).sum::<u32>()),
+                (1, 10, 10 * (26..=45).sum::<u32>())
+            ]
+        );
+
+        let (nums, mut expected_cards) = parse_input(input);
+        for num in 1..=5 {
+            mark_cards(&mut expected_cards, num);
+        }
+        let expected = sort_into_winners(nums, expected_cards);
+        assert_eq!(
+            expected
+                .iter()
+                .map(|(card, num)| (*num, *num as u32 * card.sum_unmarked()))
+                .collect::<Vec<_>>(),
+            indexed
+                .iter()
+                .map(|(_, num, score)| (*num, *score))
+                .collect::<Vec<_>>()
+        );
+
+        let events = record_game(&[6], &cards);
+        assert_eq!(
+            events.last(),
+            Some(&BingoEvent::Win {
+                card: 0,
+                score: 6 * (7..=25).sum::<u32>()
+            })
+        );
+    }
+
+    /// Plays a game with a large number of generated cards, which would be
+    /// impractically slow if every card were rescanned on every draw.
+    #[test]
+    fn test_engine_many_cards() {
+        let num_cards = 200_000;
+        let cards = (0..num_cards)
+            .map(|k| {
+                let mut nums = [[0_u8; 5]; 5];
+                for (i, row) in nums.iter_mut().enumerate() {
+                    for (j, n) in row.iter_mut().enumerate() {
+                        *n = ((k + 7 * i + 31 * j) % 100) as u8;
+                    }
+                }
+                BingoCard::new(nums)
+            })
+            .collect::<Vec<_>>();
+        let nums = (0..100).rev().collect::<Vec<u8>>();
+
+        let winners = sort_into_winners_indexed(&nums, &cards);
+        assert_eq!(winners.len(), num_cards);
+
+        let mut seen = vec![false; num_cards];
+        winners
+            .iter()
+            .for_each(|(card_idx, _, _)| seen[*card_idx] = true);
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    const EXAMPLE: &str = r"
+        7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+        22 13 17 11  0
+         8  2 23  4 24
+        21  9 14 16  7
+         6 10  3 18  5
+         1 12 20 15 19
+
+         3 15  0  2 22
+         9 18 13 17  5
+        19  8  7 25 23
+        20 11 10 24  4
+        14 21 16 12  6
+
+        14 21 17 24  4
+        10 16 15  9 19
+        18  8 23 26 20
+        22 11 13  6  5
+         2  0 12  3  7";
+
+    #[test]
+    fn test_record_game() {
+        let (nums, cards) = parse_input(EXAMPLE);
+        let events = record_game(&nums[..11], &cards);
+
+        assert_eq!(events[0], BingoEvent::Draw { turn: 0, num: 7 });
+        assert_eq!(
+            events[1],
+            BingoEvent::Mark {
+                card: 0,
+                row: 2,
+                col: 4
+            }
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, BingoEvent::Draw { .. }))
+                .count(),
+            11
+        );
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, BingoEvent::Complete { .. })));
+
+        // 24 completes the top row of the third card
+        let events = record_game(&nums[..12], &cards);
+        let tail = &events[events.len() - 6..];
+        assert_eq!(
+            tail,
+            &[
+                BingoEvent::Draw { turn: 11, num: 24 },
+                BingoEvent::Mark {
+                    card: 0,
+                    row: 1,
+                    col: 4
+                },
+                BingoEvent::Mark {
+                    card: 1,
+                    row: 3,
+                    col: 3
+                },
+                BingoEvent::Mark {
+                    card: 2,
+                    row: 0,
+                    col: 3
+                },
+                BingoEvent::Complete {
+                    card: 2,
+                    line: BingoLine::Row(0)
+                },
+                BingoEvent::Win {
+                    card: 2,
+                    score: 4512
+                },
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_record_game_win() {
+        let (nums, cards) = parse_input(EXAMPLE);
+        let events = record_game(&nums, &cards);
+
+        let first_win = events
+            .iter()
+            .position(|e| matches!(e, BingoEvent::Win { .. }))
+            .unwrap();
+        assert_eq!(
+            events[first_win - 1],
+            BingoEvent::Complete {
+                card: 2,
+                line: BingoLine::Row(0)
+            }
+        );
+        assert_eq!(
+            events[first_win],
+            BingoEvent::Win {
+                card: 2,
+                score: 4512
+            }
+        );
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+        let (nums, cards) = parse_input(EXAMPLE);
+        let events = record_game(&nums, &cards);
+        let serialized = write_events(&events);
+        assert!(serialized.starts_with("draw 0 7\nmark 0 2 4\n"));
+        assert_eq!(parse_events(&serialized), Ok(events));
+    }
+
+    #[test]
+    fn test_parse_events_invalid() {
+        assert_eq!(
+            parse_events("draw 0 7\ncomplete 1 diagonal 0\n"),
+            Err(ParseBingoEventError {
+                text: "complete 1 diagonal 0".to_string()
+            })
+        );
+        assert!(parse_events("draw 0 700").is_err());
+        assert!(parse_events("mark 1 2").is_err());
+    }
+
+    #[test]
+    fn test_replay_render() {
+        let (nums, cards) = parse_input(EXAMPLE);
+        let events = record_game(&nums, &cards);
+        let mut replay = BingoReplay::new(&cards, &events);
+
+        for _ in 0..12 {
+            assert!(matches!(
+                replay.step_draw().unwrap().first(),
+                Some(BingoEvent::Draw { .. })
+            ));
+        }
+        assert_eq!(replay.last_drawn, Some(24));
+        assert!(replay.is_marked(2, 0, 0));
+        assert!(!replay.is_marked(2, 1, 0));
+
+        let rendered = replay.render(Highlight::Brackets);
+        let first_line = rendered.lines().next().unwrap();
+        assert_eq!(
+            first_line,
+            " 22  13 [17][11][ 0]          3  15 [ 0][ 2] 22         [14][21][17][24][ 4]"
+        );
+
+        let rendered = replay.render(Highlight::Ansi);
+        let last_line = rendered.lines().last().unwrap();
+        assert_eq!(
+            last_line,
+            " 1 12 20 15 19        \x1b[1m14\x1b[0m \x1b[1m21\x1b[0m 16 12  6        \x1b[1m 2\x1b[0m \x1b[1m 0\x1b[0m 12  3 \x1b[1m 7\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_replay_validation() {
+        let (nums, mut cards) = parse_input(EXAMPLE);
+        let events = record_game(&nums, &cards);
+        let mut replay = BingoReplay::new(&cards, &events);
+        while replay.step().unwrap().is_some() {}
+        assert_eq!(replay.position, events.len());
+
+        // a replay picks up the marks the cards already carry.
+        mark_cards(&mut cards, 22);
+        let events = record_game(&nums, &cards);
+        let mut replay = BingoReplay::new(&cards, &events);
+        assert!(replay.is_marked(0, 0, 0));
+        while replay.step().unwrap().is_some() {}
+
+        let bad = [
+            BingoEvent::Draw { turn: 0, num: 7 },
+            BingoEvent::Mark {
+                card: 3,
+                row: 0,
+                col: 0,
+            },
+            BingoEvent::Mark {
+                card: 0,
+                row: 0,
+                col: 5,
+            },
+            BingoEvent::Mark {
+                card: 0,
+                row: 0,
+                col: 0,
+            },
+            BingoEvent::Complete {
+                card: 0,
+                line: BingoLine::Row(0),
+            },
+            BingoEvent::Win { card: 0, score: 1 },
+        ];
+        for (i, event) in bad.iter().enumerate().skip(1) {
+            let log = [bad[0], *event];
+            let mut replay = BingoReplay::new(&cards, &log);
+            assert_eq!(replay.step(), Ok(Some(bad[0])));
+            assert_eq!(
+                replay.step(),
+                Err(ReplayError {
+                    position: 1,
+                    event: bad[i]
+                })
+            );
+            assert_eq!(replay.position, 1);
+        }
+
+        // logs whose last event cannot have happened
+        let draw = |turn, num| BingoEvent::Draw { turn, num };
+        let unmarked_sum = cards[0]
+            .nums
+            .iter()
+            .flatten()
+            .map(|n| *n as u32)
+            .sum::<u32>()
+            - 22;
+        let impossible = [
+            // draws out of turn
+            vec![draw(1, 7)],
+            vec![draw(0, 7), draw(0, 4)],
+            vec![draw(0, 7), draw(2, 4)],
+            // 22 is already marked on the first card
+            vec![
+                draw(0, 22),
+                BingoEvent::Mark {
+                    card: 0,
+                    row: 0,
+                    col: 0,
+                },
+            ],
+            // the right score, but no complete line
+            vec![
+                draw(0, 7),
+                BingoEvent::Mark {
+                    card: 0,
+                    row: 2,
+                    col: 4,
+                },
+                BingoEvent::Win {
+                    card: 0,
+                    score: 7 * (unmarked_sum - 7),
+                },
+            ],
+        ];
+        for log in impossible.iter() {
+            let mut replay = BingoReplay::new(&cards, log);
+            for event in &log[..log.len() - 1] {
+                assert_eq!(replay.step(), Ok(Some(*event)));
+            }
+            assert_eq!(
+                replay.step(),
+                Err(ReplayError {
+                    position: log.len() - 1,
+                    event: *log.last().unwrap()
+                })
+            );
+        }
+    }
+
+    /// Builds a card from the given rows, padding with numbers that will never
+    /// be drawn.
+    fn sparse_card(rows: &[(usize, [u8; 5])], cols: &[(usize, [u8; 5])], filler: u8) -> BingoCard {
+        let mut nums = [[0_u8; 5]; 5];
+        for (k, cell) in nums.iter_mut().flatten().enumerate() {
+            *cell = filler + k as u8;
+        }
+        for (i, row) in rows {
+            nums[*i] = *row;
+        }
+        for (j, col) in cols {
+            for i in 0..5 {
+                nums[i][*j] = col[i];
+            }
+        }
+        BingoCard::new(nums)
+    }
+
+    fn small_game() -> (Vec<BingoCard>, Vec<u8>) {
+        let cards = vec![
+            sparse_card(&[(0, [1, 2, 3, 4, 5])], &[], 100),
+            sparse_card(&[], &[(2, [3, 4, 5, 6, 7])], 130),
+            sparse_card(&[(4, [4, 5, 6, 7, 8])], &[(0, [1, 2, 3, 6, 4])], 160),
+        ];
+        (cards, (1..=8).collect())
+    }
+
+    #[test]
+    fn test_exhaustive_analysis() {
+        let (cards, pool) = small_game();
+        let analysis = analyze_draw_orders(&cards, &pool, AnalysisMode::Exhaustive).unwrap();
+        assert_eq!(analysis.games, 40320);
+        assert!((analysis.first_win.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((analysis.last_win.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // every card can win from this pool, and needs at least five draws
+        for expected in analysis.expected_draws.iter() {
+            let expected = expected.unwrap();
+            assert!((5.0..=8.0).contains(&expected));
+        }
+    }
+
+    #[test]
+    fn test_monte_carlo_agrees_with_exhaustive() {
+        let (cards, pool) = small_game();
+        let exact = analyze_draw_orders(&cards, &pool, AnalysisMode::Exhaustive).unwrap();
+        let estimate = analyze_draw_orders(
+            &cards,
+            &pool,
+            AnalysisMode::MonteCarlo {
+                trials: 20_000,
+                seed: 2021,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(estimate.games, 20_000);
+        for card_idx in 0..cards.len() {
+            assert!((exact.first_win[card_idx] - estimate.first_win[card_idx]).abs() < 0.02);
+            assert!((exact.last_win[card_idx] - estimate.last_win[card_idx]).abs() < 0.02);
+            let exact_draws = exact.expected_draws[card_idx].unwrap();
+            let estimated_draws = estimate.expected_draws[card_idx].unwrap();
+            assert!((exact_draws - estimated_draws).abs() < 0.05);
+        }
+        assert_eq!(exact.best_to_win_first(), estimate.best_to_win_first());
+        assert_eq!(exact.best_to_win_last(), estimate.best_to_win_last());
+    }
+
+    #[test]
+    fn test_expected_draws_to_win() {
+        let (cards, pool) = small_game();
+        let exact = analyze_draw_orders(&cards, &pool, AnalysisMode::Exhaustive).unwrap();
+        for (card, expected) in cards.iter().zip(exact.expected_draws.iter()) {
+            let computed = expected_draws_to_win(card, &pool).unwrap();
+            assert!((computed - expected.unwrap()).abs() < 1e-9);
+        }
+
+        // a single line of 5 from a pool of 5 always takes all 5 draws
+        assert_eq!(
+            expected_draws_to_win(&cards[0], &[1, 2, 3, 4, 5]),
+            Some(5.0)
+        );
+        assert_eq!(expected_draws_to_win(&cards[0], &[1, 2, 3, 4]), None);
+
+        // marked cells count as drawn, and agree with the engine
+        let (mut marked, _) = small_game();
+        mark_cards(&mut marked, 3);
+        let exact = analyze_draw_orders(&marked, &pool, AnalysisMode::Exhaustive).unwrap();
+        for (card, expected) in marked.iter().zip(exact.expected_draws.iter()) {
+            let computed = expected_draws_to_win(card, &pool).unwrap();
+            assert!((computed - expected.unwrap()).abs() < 1e-9);
+        }
+        assert_eq!(expected_draws_to_win(&marked[0], &[1, 2, 4, 5]), Some(4.0));
+        mark_cards(&mut marked, 1);
+        mark_cards(&mut marked, 2);
+        mark_cards(&mut marked, 4);
+        mark_cards(&mut marked, 5);
+        assert_eq!(expected_draws_to_win(&marked[0], &pool), Some(0.0));
+
+        // including a card whose top row is already complete
+        let exact = analyze_draw_orders(&marked, &pool, AnalysisMode::Exhaustive).unwrap();
+        assert_eq!(exact.expected_draws[0], Some(0.0));
+        assert_eq!(exact.first_win[0], 1.0);
+        for (card, expected) in marked.iter().zip(exact.expected_draws.iter()) {
+            let computed = expected_draws_to_win(card, &pool).unwrap();
+            assert!((computed - expected.unwrap()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_analysis_errors() {
+        let (cards, pool) = small_game();
+        assert_eq!(
+            analyze_draw_orders(&[], &pool, AnalysisMode::Exhaustive).unwrap_err(),
+            AnalysisError::NoCards
+        );
+        assert_eq!(
+            analyze_draw_orders(
+                &cards,
+                &pool,
+                AnalysisMode::MonteCarlo { trials: 0, seed: 1 }
+            )
+            .unwrap_err(),
+            AnalysisError::NoTrials
+        );
+        let pool = (1..=11).collect::<Vec<_>>();
+        assert_eq!(
+            analyze_draw_orders(&cards, &pool, AnalysisMode::Exhaustive).unwrap_err(),
+            AnalysisError::PoolTooLarge(11)
+        );
+    }
+
+    #[test]
+    fn test_analyze_puzzle_cards() {
+        let (nums, cards) = parse_input(&read_puzzle_input("day4-puzzle-input.txt"));
+        let analysis = analyze_draw_orders(
+            &cards,
+            &nums,
+            AnalysisMode::MonteCarlo {
+                trials: 200,
+                seed: 4,
+            },
+        )
+        .unwrap();
+
+        assert!((analysis.first_win.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((analysis.last_win.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(analysis.expected_draws.iter().all(|d| d.is_some()));
+        assert!(analysis.best_to_win_first() < cards.len());
+    }
 }
@@ -88,20 +88,47 @@
 //!
 //! Consider all of the lines. At how many points do at least two lines overlap?
 
-use std::convert::TryFrom;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
 use std::str::FromStr;
 
-/// Defines a line by its inclusive endpoints.
-#[derive(Copy, Clone, Debug)]
+/// Defines a line by its inclusive endpoints.  Coordinates may be negative.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 struct Line {
-    x1: usize,
-    y1: usize,
-    x2: usize,
-    y2: usize,
+    x1: i64,
+    y1: i64,
+    x2: i64,
+    y2: i64,
+}
+
+/// The kinds of line a vent report may contain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LineKind {
+    /// y1 = y2.  A line that is a single point is considered horizontal.
+    Horizontal,
+    /// x1 = x2, but y1 != y2.
+    Vertical,
+    /// A diagonal at exactly 45 degrees.
+    Diagonal,
+    /// Any other slope.
+    Other,
 }
 
 impl Line {
+    fn kind(&self) -> LineKind {
+        if self.is_horizontal() {
+            LineKind::Horizontal
+        } else if self.is_vertial() {
+            LineKind::Vertical
+        } else if self.is_diagonal() {
+            LineKind::Diagonal
+        } else {
+            LineKind::Other
+        }
+    }
+
     fn is_vertial(&self) -> bool {
         self.x1 == self.x2
     }
@@ -109,6 +136,36 @@ impl Line {
     fn is_horizontal(&self) -> bool {
         self.y1 == self.y2
     }
+
+    /// Whether the line covers the point (x, y) when rasterized with
+    /// `rasterization`.
+    fn covers(&self, (x, y): (i64, i64), rasterization: Rasterization) -> bool {
+        let in_box = x >= self.x1.min(self.x2)
+            && x <= self.x1.max(self.x2)
+            && y >= self.y1.min(self.y2)
+            && y <= self.y1.max(self.y2);
+        if !in_box {
+            return false;
+        }
+
+        let on_line = (self.x2 - self.x1) as i128 * (y - self.y1) as i128
+            == (self.y2 - self.y1) as i128 * (x - self.x1) as i128;
+        if self.is_vertial() || self.is_horizontal() || self.is_diagonal() {
+            on_line
+        } else {
+            match rasterization {
+                Rasterization::Lattice => on_line,
+                _ => RasterIterator::new(*self, rasterization).any(|p| p == (x, y)),
+            }
+        }
+    }
+
+    /// Whether the line is a diagonal at exactly 45 degrees.
+    fn is_diagonal(&self) -> bool {
+        let dx = self.x2 - self.x1;
+        let dy = self.y2 - self.y1;
+        dx != 0 && dx.abs() == dy.abs()
+    }
 }
 
 struct HorizontalIterator {
@@ -137,17 +194,17 @@ impl HorizontalIterator {
                 y2: line.y2,
             },
             next: 0,
-            size: max - min + 1,
+            size: (max - min + 1) as usize,
         }
     }
 }
 
 impl Iterator for HorizontalIterator {
-    type Item = (usize, usize);
+    type Item = (i64, i64);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.next < self.size {
-            let x = self.line.x1 + self.next;
+            let x = self.line.x1 + self.next as i64;
             let y = self.line.y1;
             self.next += 1;
             Some((x, y))
@@ -189,18 +246,18 @@ impl VerticalIterator {
                 y2: max,
             },
             next: 0,
-            size: max - min + 1,
+            size: (max - min + 1) as usize,
         }
     }
 }
 
 impl Iterator for VerticalIterator {
-    type Item = (usize, usize);
+    type Item = (i64, i64);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.next < self.size {
             let x = self.line.x1;
-            let y = self.line.y1 + self.next;
+            let y = self.line.y1 + self.next as i64;
             self.next += 1;
             Some((x, y))
         } else {
@@ -224,29 +281,29 @@ struct DiagonalIterator {
 impl DiagonalIterator {
     fn new(line: Line) -> Self {
         assert_eq!(
-            (line.y2 as i32 - line.y1 as i32).abs(),
-            (line.x2 as i32 - line.x1 as i32).abs(),
+            (line.y2 - line.y1).abs(),
+            (line.x2 - line.x1).abs(),
             "Line is not 45-degree diagonal"
         );
 
         Self {
             line,
             next: 0,
-            size: usize::try_from((line.x2 as i32 - line.x1 as i32).abs() + 1).unwrap(),
+            size: ((line.x2 - line.x1).abs() + 1) as usize,
         }
     }
 }
 
 impl Iterator for DiagonalIterator {
-    type Item = (usize, usize);
+    type Item = (i64, i64);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.next < self.size {
-            let n = self.next as i32;
-            let x = self.line.x1 as i32 + if self.line.x1 < self.line.x2 { n } else { -n };
-            let y = self.line.y1 as i32 + if self.line.y1 < self.line.y2 { n } else { -n };
+            let n = self.next as i64;
+            let x = self.line.x1 + if self.line.x1 < self.line.x2 { n } else { -n };
+            let y = self.line.y1 + if self.line.y1 < self.line.y2 { n } else { -n };
             self.next += 1;
-            Some((usize::try_from(x).unwrap(), usize::try_from(y).unwrap()))
+            Some((x, y))
         } else {
             None
         }
@@ -259,78 +316,1088 @@ impl From<Line> for DiagonalIterator {
     }
 }
 
-/// Parses the input
-fn parse_input(input: &str) -> Vec<Line> {
+/// How lines that are not horizontal, vertical or 45-degree diagonal are
+/// converted into points.  The three kinds of line the puzzle promises always
+/// cover the same points regardless of the choice, and take a fast path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Rasterization {
+    /// Only the integer points that lie exactly on the line.  A line from 0,0
+    /// to 4,2 covers 0,0, 2,1 and 4,2.
+    Lattice,
+    /// One point per step along the longer axis, the one nearest the line
+    /// (Bresenham's algorithm).  A line from 0,0 to 4,2 covers 0,0, 1,0 (or
+    /// 1,1), 2,1, 3,1 (or 3,2) and 4,2.
+    Bresenham,
+    /// Every point whose unit cell the line passes through the interior of.
+    /// Cells the line only touches at a corner are not covered.
+    Supercover,
+}
+
+/// Iterates over the points of a line of arbitrary slope, according to a
+/// [`Rasterization`].  Points are produced in order from (x1, y1) to (x2, y2).
+struct RasterIterator {
+    rasterization: Rasterization,
+    x: i64,
+    y: i64,
+    /// Unit step in x and y (-1, 0 or 1).
+    sx: i64,
+    sy: i64,
+    /// Absolute extent of the line along each axis.
+    nx: i64,
+    ny: i64,
+    /// Progress along each axis, for supercover.
+    ix: i64,
+    iy: i64,
+    /// Error term, for Bresenham.
+    err: i64,
+    /// Number of points still to be produced, for lattice and Bresenham.
+    remaining: usize,
+    done: bool,
+}
+
+impl RasterIterator {
+    fn new(line: Line, rasterization: Rasterization) -> Self {
+        let dx = line.x2 - line.x1;
+        let dy = line.y2 - line.y1;
+        let (nx, ny) = (dx.abs(), dy.abs());
+
+        let (sx, sy, remaining) = match rasterization {
+            Rasterization::Lattice => {
+                // step between consecutive lattice points on the line
+                let g = gcd(nx, ny);
+                (dx / g.max(1), dy / g.max(1), g as usize + 1)
+            }
+            Rasterization::Bresenham => (dx.signum(), dy.signum(), nx.max(ny) as usize + 1),
+            Rasterization::Supercover => (dx.signum(), dy.signum(), 0),
+        };
+
+        Self {
+            rasterization,
+            x: line.x1,
+            y: line.y1,
+            sx,
+            sy,
+            nx,
+            ny,
+            ix: 0,
+            iy: 0,
+            err: nx - ny,
+            remaining,
+            done: false,
+        }
+    }
+
+    /// Advances to the next point, returning false once past the end.
+    fn advance(&mut self) -> bool {
+        match self.rasterization {
+            Rasterization::Lattice => {
+                self.remaining -= 1;
+                self.x += self.sx;
+                self.y += self.sy;
+                self.remaining > 0
+            }
+            Rasterization::Bresenham => {
+                self.remaining -= 1;
+                let e2 = 2 * self.err;
+                if e2 > -self.ny {
+                    self.err -= self.ny;
+                    self.x += self.sx;
+                }
+                if e2 < self.nx {
+                    self.err += self.nx;
+                    self.y += self.sy;
+                }
+                self.remaining > 0
+            }
+            Rasterization::Supercover => {
+                if self.ix == self.nx && self.iy == self.ny {
+                    return false;
+                }
+
+                // compare where the line next crosses a vertical cell boundary,
+                // (ix + 1/2) / nx, against a horizontal one, (iy + 1/2) / ny.
+                let cross_x = (1 + 2 * self.ix) * self.ny;
+                let cross_y = (1 + 2 * self.iy) * self.nx;
+                if cross_x <= cross_y {
+                    self.ix += 1;
+                    self.x += self.sx;
+                }
+                if cross_y <= cross_x {
+                    self.iy += 1;
+                    self.y += self.sy;
+                }
+                true
+            }
+        }
+    }
+}
+
+impl Iterator for RasterIterator {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let point = (self.x, self.y);
+        self.done = !self.advance();
+        Some(point)
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The ways in which a line of the vent report can be malformed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LineErrorKind {
+    /// There is no `->` between the two endpoints.
+    MissingArrow,
+    /// There is more than one `->`.
+    ExtraArrow,
+    /// An endpoint is not of the form `x,y`.
+    MalformedPoint,
+    /// A coordinate is not an integer.
+    InvalidCoordinate,
+}
+
+impl Display for LineErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LineErrorKind::MissingArrow => "expected `x1,y1 -> x2,y2`",
+            LineErrorKind::ExtraArrow => "more than one `->`",
+            LineErrorKind::MalformedPoint => "endpoint is not of the form `x,y`",
+            LineErrorKind::InvalidCoordinate => "coordinate is not an integer",
+        })
+    }
+}
+
+/// Error returned when a line of the vent report cannot be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ParseLineError {
+    /// The 1-based line number within the report.
+    line_number: usize,
+    /// The offending line.
+    text: String,
+    kind: LineErrorKind,
+}
+
+impl Display for ParseLineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: {}: {:?}",
+            self.line_number, self.kind, self.text
+        )
+    }
+}
+
+/// Parses an `x,y` endpoint.
+fn parse_point(s: &str) -> Result<(i64, i64), LineErrorKind> {
+    let (x, y) = s.split_once(',').ok_or(LineErrorKind::MalformedPoint)?;
+    if y.contains(',') {
+        return Err(LineErrorKind::MalformedPoint);
+    }
+
+    let parse = |c: &str| i64::from_str(c.trim()).map_err(|_| LineErrorKind::InvalidCoordinate);
+    Ok((parse(x)?, parse(y)?))
+}
+
+impl FromStr for Line {
+    type Err = LineErrorKind;
+
+    /// Parses a line of the form `x1,y1 -> x2,y2`.  Whitespace around the
+    /// numbers is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, second) = s.split_once("->").ok_or(LineErrorKind::MissingArrow)?;
+        if second.contains("->") {
+            return Err(LineErrorKind::ExtraArrow);
+        }
+
+        let (x1, y1) = parse_point(first)?;
+        let (x2, y2) = parse_point(second)?;
+        Ok(Line { x1, y1, x2, y2 })
+    }
+}
+
+/// Parses the vent report, skipping blank lines, and reports the first
+/// malformed line along with its line number.
+fn parse_lines(input: &str) -> Result<Vec<Line>, ParseLineError> {
     input
         .lines()
-        .filter(|l| !l.trim().is_empty())
-        .map(|l| {
-            let mut toks = l.split(',');
-            let x1 = usize::from_str(toks.next().unwrap().trim()).unwrap();
-            let mut inner = toks.next().unwrap().split("->");
-            let y1 = usize::from_str(inner.next().unwrap().trim()).unwrap();
-            let x2 = usize::from_str(inner.next().unwrap().trim()).unwrap();
-            let y2 = usize::from_str(toks.next().unwrap().trim()).unwrap();
-
-            Line { x1, x2, y1, y2 }
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            Line::from_str(l).map_err(|kind| ParseLineError {
+                line_number: i + 1,
+                text: l.to_string(),
+                kind,
+            })
         })
         .collect()
 }
 
+/// Parses the input
+fn parse_input(input: &str) -> Vec<Line> {
+    parse_lines(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Side length of the square tiles that make up a sparse [`Cover`].
+const TILE_SIZE: usize = 64;
+
+/// A dense cover is used when its bounding box has at most this many points
+/// for every point the lines are expected to cover.  Sparser vent fields are
+/// stored in tiles.
+const MAX_DENSE_AREA_PER_POINT: u128 = 16;
+
+/// Storage for the coverage counts of a [`Cover`].
+enum Counts {
+    /// One count per point of the bounding box, in row-major order.
+    Dense(Vec<usize>),
+    /// Square tiles of `TILE_SIZE * TILE_SIZE` counts in row-major order,
+    /// keyed by tile coordinate, allocated as points are covered.
+    Sparse(HashMap<(i64, i64), Vec<usize>>),
+}
+
+impl Counts {
+    /// Iterates over all stored counts.  Points that have never been covered
+    /// may or may not be included (as zeros).
+    fn iter(&self) -> Box<dyn Iterator<Item = &usize> + '_> {
+        match self {
+            Counts::Dense(counts) => Box::new(counts.iter()),
+            Counts::Sparse(tiles) => Box::new(tiles.values().flat_map(|t| t.iter())),
+        }
+    }
+}
+
+/// Counts how many lines cover each point.
+///
+/// Points are addressed as (x, y), where x is the column, increasing to the
+/// right, and y is the row, increasing downwards, as in the puzzle's diagrams.
+/// Dense storage is row-major: the point (x, y) is stored at index
+/// `(y - min_y) * stride + (x - min_x)`, and sparse tiles use the same layout.
 struct Cover {
     /// Counts the number of intersections at each point.
-    counts: Vec<usize>,
+    counts: Counts,
+    /// Smallest x and y coordinates of the bounding box.  A dense cover's
+    /// bounding box is fixed when it is created, while a sparse cover's grows
+    /// to contain every point covered so far.
+    min_x: i64,
+    min_y: i64,
     width: usize,
     height: usize,
+    /// Number of counts in each row of storage: the width of a dense cover, or
+    /// the tile size of a sparse one.
     stride: usize,
 }
 
 impl Cover {
+    /// Creates a dense cover for points in `0..width` by `0..height`.
     fn new(width: usize, height: usize) -> Self {
+        Self::dense(0, 0, width, height)
+    }
+
+    /// Creates a dense cover for points in `min_x..min_x + width` by
+    /// `min_y..min_y + height`.
+    fn dense(min_x: i64, min_y: i64, width: usize, height: usize) -> Self {
         let size = width * height;
         Cover {
-            counts: vec![0; size],
+            counts: Counts::Dense(vec![0; size]),
+            min_x,
+            min_y,
             width,
             height,
             stride: width,
         }
     }
 
+    /// Creates a sparse cover that can hold points anywhere.
+    fn sparse() -> Self {
+        Cover {
+            counts: Counts::Sparse(HashMap::new()),
+            min_x: 0,
+            min_y: 0,
+            width: 0,
+            height: 0,
+            stride: TILE_SIZE,
+        }
+    }
+
+    fn is_dense(&self) -> bool {
+        matches!(self.counts, Counts::Dense(_))
+    }
+
+    /// Whether (x, y) lies within the bounding box.
+    fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.min_x
+            && y >= self.min_y
+            && ((x - self.min_x) as u64) < self.width as u64
+            && ((y - self.min_y) as u64) < self.height as u64
+    }
+
+    /// Index of (x, y) in dense storage, if it is within the bounding box.
+    fn dense_index(&self, x: i64, y: i64) -> Option<usize> {
+        if self.contains(x, y) {
+            Some((y - self.min_y) as usize * self.stride + (x - self.min_x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The tile containing (x, y) in sparse storage, and the index of (x, y)
+    /// within that tile.
+    fn tile_index(x: i64, y: i64) -> ((i64, i64), usize) {
+        let tile_size = TILE_SIZE as i64;
+        let tile = (x.div_euclid(tile_size), y.div_euclid(tile_size));
+        let idx = y.rem_euclid(tile_size) as usize * TILE_SIZE + x.rem_euclid(tile_size) as usize;
+        (tile, idx)
+    }
+
     /// Gets the number of lines that cover the given (x, y) point.
-    fn count(&self, x: usize, y: usize) -> usize {
-        self.counts[x * self.stride + y]
+    fn count(&self, x: i64, y: i64) -> usize {
+        match &self.counts {
+            Counts::Dense(counts) => self.dense_index(x, y).map(|i| counts[i]).unwrap_or(0),
+            Counts::Sparse(tiles) => {
+                let (tile, idx) = Self::tile_index(x, y);
+                tiles.get(&tile).map(|t| t[idx]).unwrap_or(0)
+            }
+        }
     }
 
     /// Covers the point (x, y).
-    fn cover(&mut self, x: usize, y: usize) {
-        self.counts[x * self.stride + y] += 1;
+    fn cover(&mut self, x: i64, y: i64) {
+        if let Some(idx) = self.dense_index(x, y) {
+            if let Counts::Dense(counts) = &mut self.counts {
+                counts[idx] += 1;
+                return;
+            }
+        }
+
+        let (tile, idx) = Self::tile_index(x, y);
+        match &mut self.counts {
+            Counts::Dense(_) => panic!("Point ({}, {}) is outside the dense cover", x, y),
+            Counts::Sparse(tiles) => {
+                tiles
+                    .entry(tile)
+                    .or_insert_with(|| vec![0; TILE_SIZE * TILE_SIZE])[idx] += 1;
+            }
+        }
+        self.grow_to_contain(x, y);
+    }
+
+    /// Grows the bounding box of a sparse cover to contain (x, y).
+    fn grow_to_contain(&mut self, x: i64, y: i64) {
+        if self.width == 0 {
+            self.min_x = x;
+            self.min_y = y;
+            self.width = 1;
+            self.height = 1;
+            return;
+        }
+
+        let max_x = (self.min_x + self.width as i64 - 1).max(x);
+        let max_y = (self.min_y + self.height as i64 - 1).max(y);
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.width = (max_x - self.min_x + 1) as usize;
+        self.height = (max_y - self.min_y + 1) as usize;
     }
 
+    /// The bounding box of the cover.
+    fn bounds(&self) -> Viewport {
+        Viewport {
+            min_x: self.min_x,
+            min_y: self.min_y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Iterates over every covered point and its count, in no particular
+    /// order.
+    fn iter_covered(&self) -> Box<dyn Iterator<Item = ((i64, i64), usize)> + '_> {
+        match &self.counts {
+            Counts::Dense(counts) => Box::new(
+                counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c > 0)
+                    .map(move |(i, c)| {
+                        let x = self.min_x + (i % self.stride) as i64;
+                        let y = self.min_y + (i / self.stride) as i64;
+                        ((x, y), *c)
+                    }),
+            ),
+            Counts::Sparse(tiles) => Box::new(tiles.iter().flat_map(|((tx, ty), tile)| {
+                tile.iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c > 0)
+                    .map(move |(i, c)| {
+                        let x = tx * TILE_SIZE as i64 + (i % TILE_SIZE) as i64;
+                        let y = ty * TILE_SIZE as i64 + (i / TILE_SIZE) as i64;
+                        ((x, y), *c)
+                    })
+            })),
+        }
+    }
+
+    /// Covers every point of the line, rasterizing lines of arbitrary slope
+    /// to their lattice points.
     fn add_line(&mut self, line: Line) {
-        let iter: Box<dyn Iterator<Item = (usize, usize)>> = if line.is_vertial() {
+        self.add_line_with(line, Rasterization::Lattice);
+    }
+
+    /// Covers every point of the line, rasterizing lines that are not
+    /// horizontal, vertical or 45-degree diagonal with `rasterization`.
+    fn add_line_with(&mut self, line: Line, rasterization: Rasterization) {
+        let iter: Box<dyn Iterator<Item = (i64, i64)>> = if line.is_vertial() {
             Box::new(VerticalIterator::from(line))
         } else if line.is_horizontal() {
             Box::new(HorizontalIterator::from(line))
-        } else {
+        } else if line.is_diagonal() {
             Box::new(DiagonalIterator::from(line))
+        } else {
+            Box::new(RasterIterator::new(line, rasterization))
         };
 
         iter.for_each(|p| self.cover(p.0, p.1));
     }
 }
 
+/// A rectangular window onto a [`Cover`]: the points in `min_x..min_x + width`
+/// by `min_y..min_y + height`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Viewport {
+    min_x: i64,
+    min_y: i64,
+    width: usize,
+    height: usize,
+}
+
+impl Viewport {
+    /// A viewport of the given size centered on (x, y).
+    fn centered(x: i64, y: i64, width: usize, height: usize) -> Self {
+        Self {
+            min_x: x - width as i64 / 2,
+            min_y: y - height as i64 / 2,
+            width,
+            height,
+        }
+    }
+
+    /// Iterates over the points of each row, from top to bottom.
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = (i64, i64)>> {
+        let (min_x, width) = (self.min_x, self.width as i64);
+        (self.min_y..self.min_y + self.height as i64)
+            .map(move |y| (min_x..min_x + width).map(move |x| (x, y)))
+    }
+}
+
+/// The character the puzzle uses to draw a point covered by `count` lines.
+/// Counts too large for a single digit are drawn as `#`.
+fn coverage_char(count: usize) -> char {
+    match count {
+        0 => '.',
+        1..=9 => char::from_digit(count as u32, 10).unwrap(),
+        _ => '#',
+    }
+}
+
+/// Image formats for [`Cover::write_heatmap`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ImageFormat {
+    /// Binary greyscale (P5), brighter where coverage is higher.
+    Pgm,
+    /// Binary color (P6), from black through red to yellow and white.
+    Ppm,
+}
+
+/// Maps a coverage level in `0..=255` onto a black-red-yellow-white ramp.
+fn heat_color(level: u8) -> [u8; 3] {
+    let level = level as u32 * 3;
+    let channel = |offset: u32| level.saturating_sub(offset).min(255) as u8;
+    [channel(0), channel(255), channel(510)]
+}
+
+impl Cover {
+    /// Renders the points within `viewport` in the puzzle's format: one line
+    /// per row, with the count at each point or `.` if it is not covered.
+    fn render(&self, viewport: Viewport) -> String {
+        let mut out = String::with_capacity((viewport.width + 1) * viewport.height);
+        for row in viewport.rows() {
+            out.extend(row.map(|(x, y)| coverage_char(self.count(x, y))));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Writes the points within `viewport` as a binary PGM or PPM image, one
+    /// pixel per point, scaled so the highest coverage in the viewport is
+    /// brightest.
+    fn write_heatmap(
+        &self,
+        viewport: Viewport,
+        format: ImageFormat,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let max = viewport
+            .rows()
+            .flatten()
+            .map(|(x, y)| self.count(x, y))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let magic = match format {
+            ImageFormat::Pgm => "P5",
+            ImageFormat::Ppm => "P6",
+        };
+        write!(
+            out,
+            "{}\n{} {}\n255\n",
+            magic, viewport.width, viewport.height
+        )?;
+
+        for row in viewport.rows() {
+            let mut pixels = Vec::with_capacity(3 * viewport.width);
+            for (x, y) in row {
+                let level = (self.count(x, y) * 255 / max) as u8;
+                match format {
+                    ImageFormat::Pgm => pixels.push(level),
+                    ImageFormat::Ppm => pixels.extend_from_slice(&heat_color(level)),
+                }
+            }
+            out.write_all(&pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Writes every covered point as CSV with an `x,y,count` header, ordered
+    /// by row and then column.
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut covered = self.iter_covered().collect::<Vec<_>>();
+        covered.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+
+        writeln!(out, "x,y,count")?;
+        for ((x, y), count) in covered {
+            writeln!(out, "{},{},{}", x, y, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which neighbours of a point are considered connected to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Connectivity {
+    /// The points above, below, left and right.
+    Four,
+    /// The points above, below, left and right, and the four diagonals.
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbours(&self, (x, y): (i64, i64)) -> Vec<(i64, i64)> {
+        let mut neighbours = vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)];
+        if *self == Connectivity::Eight {
+            neighbours.extend_from_slice(&[
+                (x + 1, y + 1),
+                (x + 1, y - 1),
+                (x - 1, y + 1),
+                (x - 1, y - 1),
+            ]);
+        }
+        neighbours
+    }
+}
+
+/// Whether `goal` can be reached from `start` moving between neighbours
+/// according to `connectivity` through points that are `open`.
+///
+/// Searches from both ends take turns, with the one that has seen fewer points
+/// going next, and the answer is known as soon as either runs out of points.
+/// A walled-in start or goal is therefore found after exploring about as many
+/// points as the walls enclose, however large the area outside them.
+fn connected(
+    start: (i64, i64),
+    goal: (i64, i64),
+    connectivity: Connectivity,
+    open: impl Fn((i64, i64)) -> bool,
+) -> bool {
+    if start == goal {
+        return true;
+    }
+    let mut seen = [HashSet::new(), HashSet::new()];
+    seen[0].insert(start);
+    seen[1].insert(goal);
+    let mut frontiers = [vec![start], vec![goal]];
+    loop {
+        let side = if seen[0].len() <= seen[1].len() { 0 } else { 1 };
+        if frontiers[side].is_empty() {
+            return false;
+        }
+
+        let mut next = Vec::new();
+        for p in &frontiers[side] {
+            for n in connectivity.neighbours(*p) {
+                if !open(n) {
+                    continue;
+                }
+                if seen[1 - side].contains(&n) {
+                    return true;
+                }
+                if seen[side].insert(n) {
+                    next.push(n);
+                }
+            }
+        }
+        frontiers[side] = next;
+    }
+}
+
+impl Cover {
+    /// The number of points covered by at least `k` lines.
+    fn count_at_least(&self, k: usize) -> usize {
+        assert!(k > 0, "Every point is covered by at least 0 lines");
+        self.iter_covered().filter(|(_, c)| *c >= k).count()
+    }
+
+    /// The points covered by at least `k` lines, ordered by row and then
+    /// column.
+    fn points_at_least(&self, k: usize) -> Vec<(i64, i64)> {
+        assert!(k > 0, "Every point is covered by at least 0 lines");
+        let mut points = self
+            .iter_covered()
+            .filter(|(_, c)| *c >= k)
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        points.sort_unstable_by_key(|(x, y)| (*y, *x));
+        points
+    }
+
+    /// The highest coverage of any point, and every point with that coverage
+    /// ordered by row and then column, or `None` if nothing is covered.
+    fn max_coverage(&self) -> Option<(usize, Vec<(i64, i64)>)> {
+        let max = self.iter_covered().map(|(_, c)| c).max()?;
+        Some((max, self.points_at_least(max)))
+    }
+
+    /// Groups the points covered by at least `k` lines into connected danger
+    /// zones.  Zones are returned largest first, and the points of each zone
+    /// are ordered by row and then column.
+    fn danger_zones(&self, k: usize, connectivity: Connectivity) -> Vec<Vec<(i64, i64)>> {
+        let mut unvisited = self.points_at_least(k).into_iter().collect::<HashSet<_>>();
+        let mut zones = Vec::new();
+
+        while let Some(start) = unvisited.iter().next().cloned() {
+            unvisited.remove(&start);
+            let mut zone = vec![start];
+            let mut frontier = vec![start];
+            while let Some(p) = frontier.pop() {
+                for n in connectivity.neighbours(p) {
+                    if unvisited.remove(&n) {
+                        zone.push(n);
+                        frontier.push(n);
+                    }
+                }
+            }
+            zone.sort_unstable_by_key(|(x, y)| (*y, *x));
+            zones.push(zone);
+        }
+
+        zones.sort_by_key(|zone| (Reverse(zone.len()), zone[0].1, zone[0].0));
+        zones
+    }
+}
+
+/// A route through the vent field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Route {
+    /// Every point visited, from the start to the goal inclusive.
+    path: Vec<(i64, i64)>,
+    /// The sum of the coverage of every point on the path.
+    exposure: usize,
+}
+
+impl Cover {
+    /// Finds the route from `start` to `goal` with the least exposure to vents,
+    /// moving between neighbours according to `connectivity` and never
+    /// entering a point covered by more than `max_coverage` lines, if given.
+    /// Among routes with equal exposure, the one with the fewest steps is
+    /// chosen.  Returns `None` if the goal cannot be reached.
+    ///
+    /// Routes stay within the bounding box of the cover, extended to contain
+    /// `start` and `goal` and a margin of one point on every side so that
+    /// routes can go around vents on its edge.  Only the points the search
+    /// reaches are stored, so a large, sparse cover costs no more than a small
+    /// one when the goal is close.  When `max_coverage` may wall off the
+    /// goal, it is first checked to be reachable at all (see [`connected`]),
+    /// so that an unreachable goal does not flood the whole area.
+    fn safest_route(
+        &self,
+        start: (i64, i64),
+        goal: (i64, i64),
+        connectivity: Connectivity,
+        max_coverage: Option<usize>,
+    ) -> Option<Route> {
+        let min_x = self.min_x.min(start.0).min(goal.0) - 1;
+        let min_y = self.min_y.min(start.1).min(goal.1) - 1;
+        let max_x = (self.min_x + self.width as i64 - 1)
+            .max(start.0)
+            .max(goal.0)
+            + 1;
+        let max_y = (self.min_y + self.height as i64 - 1)
+            .max(start.1)
+            .max(goal.1)
+            + 1;
+        let passable = |p: (i64, i64)| max_coverage.map(|max| self.count(p.0, p.1) <= max);
+        if passable(start) == Some(false) || passable(goal) == Some(false) {
+            return None;
+        }
+        let in_bounds =
+            |n: (i64, i64)| n.0 >= min_x && n.0 <= max_x && n.1 >= min_y && n.1 <= max_y;
+        if max_coverage.is_some()
+            && !connected(start, goal, connectivity, |n| {
+                in_bounds(n) && passable(n) != Some(false)
+            })
+        {
+            return None;
+        }
+
+        // Dijkstra's algorithm, with cost (exposure, steps)
+        let mut best = HashMap::new();
+        let mut previous = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        best.insert(start, (self.count(start.0, start.1), 0));
+        queue.push(Reverse((best[&start], start)));
+
+        while let Some(Reverse((cost, p))) = queue.pop() {
+            if p == goal {
+                break;
+            }
+            if cost > best[&p] {
+                continue;
+            }
+
+            for n in connectivity.neighbours(p) {
+                if !in_bounds(n) || passable(n) == Some(false) {
+                    continue;
+                }
+
+                let next_cost = (cost.0 + self.count(n.0, n.1), cost.1 + 1);
+                if next_cost < *best.get(&n).unwrap_or(&(usize::MAX, usize::MAX)) {
+                    best.insert(n, next_cost);
+                    previous.insert(n, p);
+                    queue.push(Reverse((next_cost, n)));
+                }
+            }
+        }
+
+        let exposure = best.get(&goal)?.0;
+        let mut path = vec![goal];
+        while let Some(p) = previous.get(path.last().unwrap()) {
+            path.push(*p);
+        }
+        path.reverse();
+
+        Some(Route { path, exposure })
+    }
+
+    /// Renders the points within `viewport` like [`Cover::render`], with the
+    /// points of `route` drawn as `*`, or `!` where the route is exposed to a
+    /// vent.
+    fn render_route(&self, route: &Route, viewport: Viewport) -> String {
+        let on_route = route.path.iter().cloned().collect::<HashSet<_>>();
+        let mut out = String::with_capacity((viewport.width + 1) * viewport.height);
+        for row in viewport.rows() {
+            out.extend(row.map(|(x, y)| {
+                let count = self.count(x, y);
+                match (on_route.contains(&(x, y)), count) {
+                    (true, 0) => '*',
+                    (true, _) => '!',
+                    (false, _) => coverage_char(count),
+                }
+            }));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The indices of the lines that cover `point` when rasterized with
+/// `rasterization`, i.e. the lines responsible for its coverage.
+fn lines_covering(lines: &[Line], point: (i64, i64), rasterization: Rasterization) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.covers(point, rasterization))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// The most points [`Cover`]'s `Display` impl draws in each direction; larger
+/// covers are truncated, so use [`Cover::render`] to look elsewhere.
+const MAX_DISPLAY_SIZE: usize = 1000;
+
+impl Display for Cover {
+    /// Renders the bounding box in the puzzle's format, one row at a time,
+    /// truncated to [`MAX_DISPLAY_SIZE`] points from its top-left corner.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut viewport = self.bounds();
+        viewport.width = viewport.width.min(MAX_DISPLAY_SIZE);
+        viewport.height = viewport.height.min(MAX_DISPLAY_SIZE);
+        for row in viewport.rows() {
+            let row = row
+                .map(|(x, y)| coverage_char(self.count(x, y)))
+                .collect::<String>();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
 fn build_cover(input: impl Iterator<Item = Line> + Clone) -> Cover {
-    let width = input.clone().map(|l| l.x1.max(l.x2)).max().unwrap() + 1;
-    let height = input.clone().map(|l| l.y1.max(l.y2)).max().unwrap() + 1;
-    let mut cover = Cover::new(width, height);
-    input.for_each(|l| cover.add_line(l));
+    build_cover_with(input, Rasterization::Lattice)
+}
+
+/// Builds a cover from the lines, rasterizing lines of arbitrary slope with
+/// `rasterization`.  The cover is dense if the lines are expected to cover a
+/// large enough fraction of their bounding box, and sparse otherwise.
+fn build_cover_with(
+    input: impl Iterator<Item = Line> + Clone,
+    rasterization: Rasterization,
+) -> Cover {
+    let mut cover = match bounding_box(input.clone()) {
+        Some((min_x, min_y, max_x, max_y)) => {
+            let width = (max_x - min_x) as u128 + 1;
+            let height = (max_y - min_y) as u128 + 1;
+            let points = input
+                .clone()
+                .map(|l| ((l.x2 - l.x1).abs() + (l.y2 - l.y1).abs()) as u128 + 1)
+                .sum::<u128>();
+            if width * height <= MAX_DENSE_AREA_PER_POINT * points {
+                Cover::dense(min_x, min_y, width as usize, height as usize)
+            } else {
+                Cover::sparse()
+            }
+        }
+        None => Cover::sparse(),
+    };
+
+    input.for_each(|l| cover.add_line_with(l, rasterization));
     cover
 }
 
+/// The smallest box (min_x, min_y, max_x, max_y) containing every line, or
+/// `None` if there are no lines.
+fn bounding_box(input: impl Iterator<Item = Line>) -> Option<(i64, i64, i64, i64)> {
+    input
+        .map(|l| {
+            (
+                l.x1.min(l.x2),
+                l.y1.min(l.y2),
+                l.x1.max(l.x2),
+                l.y1.max(l.y2),
+            )
+        })
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+}
+
+/// The infinite line that a horizontal, vertical or 45-degree segment lies
+/// on, identified by the quantity that is constant along it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Carrier {
+    /// y is constant.
+    Horizontal(i64),
+    /// x is constant.
+    Vertical(i64),
+    /// x - y is constant.
+    Diagonal(i64),
+    /// x + y is constant.
+    AntiDiagonal(i64),
+}
+
+impl Carrier {
+    /// The carrier of the line and the inclusive range of positions it covers
+    /// along the carrier, or `None` if the line is not horizontal, vertical or
+    /// 45-degree diagonal.  Positions are measured along y for vertical
+    /// carriers and along x for all others.
+    fn of(line: &Line) -> Option<(Carrier, i64, i64)> {
+        let carrier = if line.is_vertial() {
+            Carrier::Vertical(line.x1)
+        } else if line.is_horizontal() {
+            Carrier::Horizontal(line.y1)
+        } else if line.is_diagonal() && (line.x2 - line.x1) == (line.y2 - line.y1) {
+            Carrier::Diagonal(line.x1 - line.y1)
+        } else if line.is_diagonal() {
+            Carrier::AntiDiagonal(line.x1 + line.y1)
+        } else {
+            return None;
+        };
+
+        let (a, b) = match carrier {
+            Carrier::Vertical(_) => (line.y1, line.y2),
+            _ => (line.x1, line.x2),
+        };
+        Some((carrier, a.min(b), a.max(b)))
+    }
+
+    /// The position of the point along the carrier.
+    fn position_of(&self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Carrier::Vertical(_) => y,
+            _ => x,
+        }
+    }
+
+    /// The lattice point at which two carriers cross, if they cross at one.
+    fn crossing(&self, other: &Carrier) -> Option<(i64, i64)> {
+        use Carrier::*;
+        match (*self, *other) {
+            (Horizontal(y), Vertical(x)) | (Vertical(x), Horizontal(y)) => Some((x, y)),
+            (Horizontal(y), Diagonal(c)) | (Diagonal(c), Horizontal(y)) => Some((y + c, y)),
+            (Horizontal(y), AntiDiagonal(c)) | (AntiDiagonal(c), Horizontal(y)) => Some((c - y, y)),
+            (Vertical(x), Diagonal(c)) | (Diagonal(c), Vertical(x)) => Some((x, x - c)),
+            (Vertical(x), AntiDiagonal(c)) | (AntiDiagonal(c), Vertical(x)) => Some((x, c - x)),
+            (Diagonal(d), AntiDiagonal(a)) | (AntiDiagonal(a), Diagonal(d)) => {
+                // x - y = d and x + y = a cross at a lattice point only if d and
+                // a have the same parity.
+                if (d + a) % 2 == 0 {
+                    Some(((d + a) / 2, (a - d) / 2))
+                } else {
+                    None
+                }
+            }
+            // parallel carriers never cross
+            _ => None,
+        }
+    }
+
+    /// The carriers of each kind that pass through the point.
+    fn through((x, y): (i64, i64)) -> [Carrier; 4] {
+        [
+            Carrier::Horizontal(y),
+            Carrier::Vertical(x),
+            Carrier::Diagonal(x - y),
+            Carrier::AntiDiagonal(x + y),
+        ]
+    }
+}
+
+/// Maximal runs of positions along a carrier with the same, non-zero,
+/// coverage, as (first, last, coverage), in ascending order.
+fn coverage_runs(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64, usize)> {
+    let mut events = Vec::with_capacity(2 * intervals.len());
+    for (lo, hi) in intervals.drain(..) {
+        events.push((lo, 1_i64));
+        events.push((hi + 1, -1));
+    }
+    events.sort_unstable();
+
+    let mut runs = Vec::new();
+    let mut coverage = 0_i64;
+    let mut i = 0;
+    while i < events.len() {
+        let pos = events[i].0;
+        while i < events.len() && events[i].0 == pos {
+            coverage += events[i].1;
+            i += 1;
+        }
+        if coverage > 0 {
+            // the run extends to just before the next event
+            runs.push((pos, events[i].0 - 1, coverage as usize));
+        }
+    }
+    runs
+}
+
+/// Coverage of the given position within a carrier's runs.
+fn coverage_at(runs: &[(i64, i64, usize)], pos: i64) -> usize {
+    let idx = runs.partition_point(|run| run.1 < pos);
+    match runs.get(idx) {
+        Some((lo, _, coverage)) if *lo <= pos => *coverage,
+        _ => 0,
+    }
+}
+
+/// Counts the points covered by at least `k` lines without rasterizing them,
+/// so the cost depends only on the number of lines and not their length.
+///
+/// Lines are grouped by the carrier they lie on, where overlapping lines are
+/// merged into runs of constant coverage.  Summing the runs covered at least
+/// `k` times counts every point once per carrier, which is only wrong at the
+/// points where two carriers cross.  Those points are found by intersecting
+/// every pair of carriers and corrected using their total coverage.
+///
+/// Panics if any line is not horizontal, vertical or 45-degree diagonal.
+fn count_covered_at_least(lines: &[Line], k: usize) -> usize {
+    assert!(k > 0, "Every point is covered by at least 0 lines");
+
+    let mut intervals: HashMap<Carrier, Vec<(i64, i64)>> = HashMap::new();
+    for line in lines {
+        let (carrier, lo, hi) = Carrier::of(line)
+            .unwrap_or_else(|| panic!("{:?} is not horizontal, vertical or diagonal", line));
+        intervals.entry(carrier).or_default().push((lo, hi));
+    }
+
+    let mut carriers = intervals
+        .into_iter()
+        .map(|(carrier, intervals)| (carrier, coverage_runs(intervals)))
+        .collect::<Vec<_>>();
+    carriers.sort_unstable_by_key(|(carrier, _)| *carrier);
+    let runs_by_carrier = carriers.iter().cloned().collect::<HashMap<_, _>>();
+
+    let mut count = carriers
+        .iter()
+        .flat_map(|(_, runs)| runs.iter())
+        .filter(|(_, _, coverage)| *coverage >= k)
+        .map(|(lo, hi, _)| (hi - lo + 1) as usize)
+        .sum::<usize>();
+
+    // find the covered points where carriers cross
+    let mut crossings = HashSet::new();
+    for (i, (carrier, runs)) in carriers.iter().enumerate() {
+        for (other, other_runs) in carriers[i + 1..].iter() {
+            if let Some(p) = carrier.crossing(other) {
+                if coverage_at(runs, carrier.position_of(p)) > 0
+                    && coverage_at(other_runs, other.position_of(p)) > 0
+                {
+                    crossings.insert(p);
+                }
+            }
+        }
+    }
+
+    // each crossing was counted once for every carrier covering it at least k
+    // times, but should be counted once if its total coverage is at least k.
+    for p in crossings {
+        let coverages = Carrier::through(p)
+            .iter()
+            .map(|c| {
+                runs_by_carrier
+                    .get(c)
+                    .map(|runs| coverage_at(runs, c.position_of(p)))
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        count -= coverages.iter().filter(|c| **c >= k).count();
+        if coverages.iter().sum::<usize>() >= k {
+            count += 1;
+        }
+    }
+
+    count
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::read_puzzle_input;
+    use crate::utils::{read_puzzle_input, SplitMix64};
 
     #[test]
     fn solve_part_1() {
@@ -429,4 +1496,774 @@ mod tests {
         assert_eq!((5, 5), iter.next().unwrap());
         assert!(iter.next().is_none());
     }
+
+    fn line(x1: i64, y1: i64, x2: i64, y2: i64) -> Line {
+        Line { x1, y1, x2, y2 }
+    }
+
+    fn raster(line: Line, rasterization: Rasterization) -> Vec<(i64, i64)> {
+        RasterIterator::new(line, rasterization).collect()
+    }
+
+    #[test]
+    fn test_lattice_rasterization() {
+        assert_eq!(
+            raster(line(0, 0, 4, 2), Rasterization::Lattice),
+            vec![(0, 0), (2, 1), (4, 2)]
+        );
+        assert_eq!(
+            raster(line(6, 1, 0, 5), Rasterization::Lattice),
+            vec![(6, 1), (3, 3), (0, 5)]
+        );
+        // no lattice points between the endpoints
+        assert_eq!(
+            raster(line(1, 1, 4, 3), Rasterization::Lattice),
+            vec![(1, 1), (4, 3)]
+        );
+        assert_eq!(
+            raster(line(2, 2, 2, 2), Rasterization::Lattice),
+            vec![(2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_bresenham_rasterization() {
+        assert_eq!(
+            raster(line(0, 0, 6, 2), Rasterization::Bresenham),
+            vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 1), (5, 2), (6, 2)]
+        );
+        assert_eq!(
+            raster(line(2, 5, 0, 0), Rasterization::Bresenham),
+            vec![(2, 5), (2, 4), (1, 3), (1, 2), (0, 1), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_supercover_rasterization() {
+        // passes exactly through the corner shared by (1, 0), (2, 0), (1, 1)
+        // and (2, 1), so (2, 0) and (1, 1) are only touched and not covered
+        assert_eq!(
+            raster(line(0, 0, 3, 1), Rasterization::Supercover),
+            vec![(0, 0), (1, 0), (2, 1), (3, 1)]
+        );
+        // the same for a steep line, through the corner at (0.5, 1.5)
+        assert_eq!(
+            raster(line(0, 0, 1, 3), Rasterization::Supercover),
+            vec![(0, 0), (0, 1), (1, 2), (1, 3)]
+        );
+        // passes through the lattice point (2, 1) but no corners, so each step
+        // crosses a cell edge and covers both cells on either side of it
+        assert_eq!(
+            raster(line(0, 0, 4, 2), Rasterization::Supercover),
+            vec![(0, 0), (1, 0), (1, 1), (2, 1), (3, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            raster(line(3, 3, 0, 0), Rasterization::Supercover),
+            vec![(3, 3), (2, 2), (1, 1), (0, 0)]
+        );
+    }
+
+    /// Every rasterization agrees with the fast paths on the kinds of line the
+    /// puzzle uses.
+    #[test]
+    fn test_rasterizations_agree_on_puzzle_lines() {
+        let lines = [
+            line(0, 9, 5, 9),
+            line(9, 4, 3, 4),
+            line(7, 0, 7, 4),
+            line(2, 2, 2, 1),
+            line(8, 0, 0, 8),
+            line(5, 5, 8, 2),
+        ];
+        for l in lines.iter().cloned() {
+            let expected: Vec<_> = if l.is_vertial() {
+                VerticalIterator::from(l).collect()
+            } else if l.is_horizontal() {
+                HorizontalIterator::from(l).collect()
+            } else {
+                DiagonalIterator::from(l).collect()
+            };
+            let mut expected_sorted = expected.clone();
+            expected_sorted.sort_unstable();
+
+            for r in [
+                Rasterization::Lattice,
+                Rasterization::Bresenham,
+                Rasterization::Supercover,
+            ] {
+                let mut points = raster(l, r);
+                points.sort_unstable();
+                assert_eq!(points, expected_sorted, "{:?} with {:?}", l, r);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_cover_any_slope() {
+        let input = parse_input(
+            r"
+        0,1 -> 4,3
+        0,3 -> 4,1
+        2,0 -> 2,4",
+        );
+
+        let cover = build_cover_with(input.iter().cloned(), Rasterization::Lattice);
+        assert_eq!(cover.count(2, 2), 3);
+        assert_eq!(cover.counts.iter().filter(|c| **c >= 2).count(), 1);
+        assert_eq!(cover.counts.iter().sum::<usize>(), 11);
+
+        let cover = build_cover_with(input.iter().cloned(), Rasterization::Bresenham);
+        assert_eq!(cover.count(2, 2), 3);
+        assert_eq!(cover.counts.iter().sum::<usize>(), 15);
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        let input = parse_input(
+            r"
+        -3,-3 -> 3,3
+        -3,3 -> 3,-3
+        0,-5 -> 0,-1",
+        );
+
+        let cover = build_cover(input.into_iter());
+        assert!(cover.is_dense());
+        assert_eq!((cover.min_x, cover.min_y), (-3, -5));
+        assert_eq!((cover.width, cover.height), (7, 9));
+        assert_eq!(cover.count(0, 0), 2);
+        assert_eq!(cover.count(-3, -3), 1);
+        assert_eq!(cover.count(0, -4), 1);
+        assert_eq!(cover.count(-4, 0), 0);
+        assert_eq!(cover.count(100, 100), 0);
+    }
+
+    /// A single far-away vent must not blow up the memory needed for the
+    /// cover.
+    #[test]
+    fn test_sparse_cover() {
+        let input = parse_input(
+            r"
+        0,9 -> 5,9
+        0,9 -> 2,9
+        -1000000000,-7 -> -1000000000,-9
+        3000000000000,40 -> 2999999999999,41
+        2999999999999,41 -> 2999999999999,40",
+        );
+
+        let cover = build_cover(input.into_iter());
+        assert!(!cover.is_dense());
+        assert_eq!(cover.count(1, 9), 2);
+        assert_eq!(cover.count(-1_000_000_000, -8), 1);
+        assert_eq!(cover.count(2_999_999_999_999, 41), 2);
+        assert_eq!(cover.count(2_999_999_999_999, 39), 0);
+        assert_eq!(cover.counts.iter().filter(|c| **c >= 2).count(), 4);
+        assert_eq!((cover.min_x, cover.min_y), (-1_000_000_000, -9));
+        assert_eq!(cover.height, 51);
+    }
+
+    /// Dense and sparse storage agree on every point of the puzzle input.
+    #[test]
+    fn test_sparse_matches_dense() {
+        let input = parse_input(&read_puzzle_input("day5-puzzle-input.txt"));
+        let dense = build_cover(input.iter().cloned());
+        assert!(dense.is_dense());
+
+        let mut sparse = Cover::sparse();
+        input.iter().for_each(|l| sparse.add_line(*l));
+        assert_eq!(
+            (sparse.min_x, sparse.min_y, sparse.width, sparse.height),
+            (dense.min_x, dense.min_y, dense.width, dense.height)
+        );
+
+        for y in dense.min_y..dense.min_y + dense.height as i64 {
+            for x in dense.min_x..dense.min_x + dense.width as i64 {
+                assert_eq!(sparse.count(x, y), dense.count(x, y));
+            }
+        }
+        assert_eq!(sparse.counts.iter().filter(|c| **c >= 2).count(), 20666);
+    }
+
+    #[test]
+    fn test_count_covered_at_least_example() {
+        let input = parse_input(
+            r"
+        0,9 -> 5,9
+        8,0 -> 0,8
+        9,4 -> 3,4
+        2,2 -> 2,1
+        7,0 -> 7,4
+        6,4 -> 2,0
+        0,9 -> 2,9
+        3,4 -> 1,4
+        0,0 -> 8,8
+        5,5 -> 8,2",
+        );
+
+        let straight = input
+            .iter()
+            .cloned()
+            .filter(|l| l.is_vertial() || l.is_horizontal())
+            .collect::<Vec<_>>();
+        assert_eq!(count_covered_at_least(&straight, 2), 5);
+        assert_eq!(count_covered_at_least(&input, 2), 12);
+        assert_eq!(count_covered_at_least(&input, 3), 2);
+        assert_eq!(count_covered_at_least(&input, 4), 0);
+    }
+
+    #[test]
+    fn test_count_covered_at_least_puzzle() {
+        let input = parse_input(&read_puzzle_input("day5-puzzle-input.txt"));
+        let cover = build_cover(input.iter().cloned());
+        for k in 1..=4 {
+            let expected = cover.counts.iter().filter(|c| **c >= k).count();
+            assert_eq!(count_covered_at_least(&input, k), expected, "k = {}", k);
+        }
+
+        let straight = input
+            .iter()
+            .cloned()
+            .filter(|l| l.is_vertial() || l.is_horizontal())
+            .collect::<Vec<_>>();
+        assert_eq!(count_covered_at_least(&straight, 2), 6856);
+    }
+
+    /// Crossings of diagonals only count when they meet at a lattice point,
+    /// and a point where many carriers meet is only counted once.
+    #[test]
+    fn test_count_covered_at_least_crossings() {
+        // x - y = 0 and x + y = 3 cross at (1.5, 1.5)
+        let lines = [line(0, 0, 3, 3), line(0, 3, 3, 0)];
+        assert_eq!(count_covered_at_least(&lines, 2), 0);
+
+        // four carriers through (5, 5), plus a single point on top
+        let lines = [
+            line(0, 5, 10, 5),
+            line(5, 0, 5, 10),
+            line(0, 0, 10, 10),
+            line(0, 10, 10, 0),
+            line(5, 5, 5, 5),
+        ];
+        assert_eq!(count_covered_at_least(&lines, 2), 1);
+        assert_eq!(count_covered_at_least(&lines, 5), 1);
+        assert_eq!(count_covered_at_least(&lines, 6), 0);
+        assert_eq!(count_covered_at_least(&lines, 1), 4 * 11 - 3);
+    }
+
+    /// The cost of counting does not depend on the length of the lines.
+    #[test]
+    fn test_count_covered_at_least_long_lines() {
+        let n = 1_000_000_000_000;
+        let lines = [
+            line(-n, 0, n, 0),
+            line(0, -n, 0, n),
+            line(-n, 0, 0, 0),
+            line(-n, -n, n, n),
+        ];
+        assert_eq!(count_covered_at_least(&lines, 2), n as usize + 1);
+        assert_eq!(count_covered_at_least(&lines, 3), 1);
+    }
+
+    /// Agrees with rasterizing on pseudo-random fields of lines.
+    #[test]
+    fn test_count_covered_at_least_random() {
+        let mut rng = SplitMix64::new(12345);
+        let mut next = |bound: i64| rng.below(bound as usize) as i64 - bound / 2;
+
+        for _ in 0..20 {
+            let lines = (0..40)
+                .map(|_| {
+                    let (x1, y1) = (next(30), next(30));
+                    let len = next(20);
+                    match next(4) + 2 {
+                        0 => line(x1, y1, x1 + len, y1),
+                        1 => line(x1, y1, x1, y1 + len),
+                        2 => line(x1, y1, x1 + len, y1 + len),
+                        _ => line(x1, y1, x1 + len, y1 - len),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let cover = build_cover(lines.iter().cloned());
+            for k in 1..=3 {
+                let expected = cover.counts.iter().filter(|c| **c >= k).count();
+                assert_eq!(count_covered_at_least(&lines, k), expected);
+            }
+        }
+    }
+
+    const EXAMPLE: &str = r"
+        0,9 -> 5,9
+        8,0 -> 0,8
+        9,4 -> 3,4
+        2,2 -> 2,1
+        7,0 -> 7,4
+        6,4 -> 2,0
+        0,9 -> 2,9
+        3,4 -> 1,4
+        0,0 -> 8,8
+        5,5 -> 8,2";
+
+    #[test]
+    fn test_render_example() {
+        let input = parse_input(EXAMPLE);
+        let cover = build_cover(
+            input
+                .iter()
+                .cloned()
+                .filter(|l| l.is_vertial() || l.is_horizontal()),
+        );
+        assert_eq!(
+            cover.render(Viewport {
+                min_x: 0,
+                min_y: 0,
+                width: 10,
+                height: 10
+            }),
+            ".......1..\n\
+             ..1....1..\n\
+             ..1....1..\n\
+             .......1..\n\
+             .112111211\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             222111....\n"
+        );
+
+        let cover = build_cover(input.into_iter());
+        assert_eq!(
+            cover.to_string(),
+            "1.1....11.\n\
+             .111...2..\n\
+             ..2.1.111.\n\
+             ...1.2.2..\n\
+             .112313211\n\
+             ...1.2....\n\
+             ..1...1...\n\
+             .1.....1..\n\
+             1.......1.\n\
+             222111....\n"
+        );
+    }
+
+    #[test]
+    fn test_render_viewport() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+        assert_eq!(
+            cover.render(Viewport::centered(4, 4, 3, 3)),
+            "1.2\n\
+             231\n\
+             1.2\n"
+        );
+
+        // points outside the cover are not covered
+        assert_eq!(
+            cover.render(Viewport {
+                min_x: -2,
+                min_y: 8,
+                width: 4,
+                height: 3
+            }),
+            "..1.\n\
+             ..22\n\
+             ....\n"
+        );
+
+        let mut sparse = Cover::sparse();
+        for l in [line(0, 0, 11, 0), line(0, 0, 9, 0)] {
+            sparse.add_line(l);
+            sparse.add_line(l);
+        }
+        for _ in 0..6 {
+            sparse.add_line(line(0, 0, 0, 0));
+        }
+        assert_eq!(sparse.to_string(), "#44444444422\n");
+
+        // a huge cover is truncated rather than drawn in full
+        let mut huge = Cover::sparse();
+        huge.add_line(line(0, 0, 1, 0));
+        huge.add_line(line(
+            1_000_000_000,
+            1_000_000_000,
+            1_000_000_000,
+            1_000_000_000,
+        ));
+        let rendered = huge.to_string();
+        assert_eq!(rendered.lines().count(), MAX_DISPLAY_SIZE);
+        assert!(rendered.lines().next().unwrap().starts_with("11."));
+        assert!(rendered.lines().all(|l| l.len() == MAX_DISPLAY_SIZE));
+    }
+
+    #[test]
+    fn test_write_heatmap() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+        let viewport = Viewport {
+            min_x: 0,
+            min_y: 9,
+            width: 4,
+            height: 1,
+        };
+
+        let mut pgm = Vec::new();
+        cover
+            .write_heatmap(viewport, ImageFormat::Pgm, &mut pgm)
+            .unwrap();
+        assert_eq!(pgm, b"P5\n4 1\n255\n\xff\xff\xff\x7f");
+
+        let mut ppm = Vec::new();
+        cover
+            .write_heatmap(viewport, ImageFormat::Ppm, &mut ppm)
+            .unwrap();
+        let header = b"P6\n4 1\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(&ppm[header.len()..header.len() + 3], &[255, 255, 255]);
+        assert_eq!(&ppm[ppm.len() - 3..], &[255, 126, 0]);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let input = parse_input(
+            r"
+        -1,2 -> 1,2
+        0,3 -> 0,1",
+        );
+        let cover = build_cover(input.into_iter());
+
+        let mut csv = Vec::new();
+        cover.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "x,y,count\n0,1,1\n-1,2,1\n0,2,2\n1,2,1\n0,3,1\n"
+        );
+    }
+
+    #[test]
+    fn test_threshold_queries() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+        assert_eq!(cover.count_at_least(1), 39);
+        assert_eq!(cover.count_at_least(2), 12);
+        assert_eq!(cover.count_at_least(3), 2);
+        assert_eq!(cover.count_at_least(4), 0);
+        assert_eq!(cover.points_at_least(3), vec![(4, 4), (6, 4)]);
+        assert_eq!(cover.max_coverage(), Some((3, vec![(4, 4), (6, 4)])));
+        assert_eq!(Cover::sparse().max_coverage(), None);
+
+        let input = parse_input(&read_puzzle_input("day5-puzzle-input.txt"));
+        let cover = build_cover(input.into_iter());
+        assert_eq!(cover.count_at_least(2), 20666);
+    }
+
+    #[test]
+    fn test_lines_covering() {
+        let input = parse_input(EXAMPLE);
+        let cover = build_cover(input.iter().cloned());
+        let (max, hotspots) = cover.max_coverage().unwrap();
+
+        for hotspot in hotspots {
+            let covering = lines_covering(&input, hotspot, Rasterization::Lattice);
+            assert_eq!(covering.len(), max);
+        }
+        assert_eq!(
+            lines_covering(&input, (4, 4), Rasterization::Lattice),
+            vec![1, 2, 8]
+        );
+        assert_eq!(
+            lines_covering(&input, (6, 4), Rasterization::Lattice),
+            vec![2, 5, 9]
+        );
+        assert!(lines_covering(&input, (9, 9), Rasterization::Lattice).is_empty());
+
+        let steep = [line(0, 0, 4, 2)];
+        assert!(lines_covering(&steep, (1, 0), Rasterization::Lattice).is_empty());
+        assert_eq!(
+            lines_covering(&steep, (1, 0), Rasterization::Supercover),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn test_danger_zones() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+        let zones = cover.danger_zones(2, Connectivity::Four);
+        assert_eq!(
+            zones,
+            vec![
+                vec![(7, 3), (6, 4), (7, 4)],
+                vec![(0, 9), (1, 9), (2, 9)],
+                vec![(3, 4), (4, 4)],
+                vec![(7, 1)],
+                vec![(2, 2)],
+                vec![(5, 3)],
+                vec![(5, 5)],
+            ]
+        );
+
+        let zones = cover.danger_zones(2, Connectivity::Eight);
+        assert_eq!(zones.len(), 4);
+        assert_eq!(
+            zones[0],
+            vec![(5, 3), (7, 3), (3, 4), (4, 4), (6, 4), (7, 4), (5, 5)]
+        );
+        assert_eq!(zones.iter().map(|z| z.len()).sum::<usize>(), 12);
+    }
+
+    /// Checks that the route is connected, starts and ends in the right
+    /// places, and that its exposure is the sum of the coverage along it.
+    fn check_route(cover: &Cover, route: &Route, start: (i64, i64), goal: (i64, i64)) {
+        assert_eq!(route.path.first(), Some(&start));
+        assert_eq!(route.path.last(), Some(&goal));
+        assert!(route
+            .path
+            .windows(2)
+            .all(|w| (w[0].0 - w[1].0).abs().max((w[0].1 - w[1].1).abs()) == 1));
+        assert_eq!(
+            route
+                .path
+                .iter()
+                .map(|(x, y)| cover.count(*x, *y))
+                .sum::<usize>(),
+            route.exposure
+        );
+    }
+
+    #[test]
+    fn test_safest_route() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+
+        // slipping diagonally between vents avoids them completely
+        let route = cover
+            .safest_route((4, 0), (4, 3), Connectivity::Eight, None)
+            .unwrap();
+        check_route(&cover, &route, (4, 0), (4, 3));
+        assert_eq!(route.exposure, 0);
+        assert_eq!(route.path.len(), 4);
+
+        let route = cover
+            .safest_route((4, 0), (4, 3), Connectivity::Four, None)
+            .unwrap();
+        check_route(&cover, &route, (4, 0), (4, 3));
+        assert_eq!(route.exposure, 1);
+
+        // tightening the threshold can only make the route more exposed
+        let mut last_exposure = 0;
+        for max_coverage in [None, Some(2), Some(1)] {
+            let route = cover
+                .safest_route((4, 3), (4, 6), Connectivity::Four, max_coverage)
+                .unwrap();
+            check_route(&cover, &route, (4, 3), (4, 6));
+            if let Some(max) = max_coverage {
+                assert!(route.path.iter().all(|(x, y)| cover.count(*x, *y) <= max));
+            }
+            assert!(route.exposure >= last_exposure);
+            last_exposure = route.exposure;
+        }
+    }
+
+    #[test]
+    fn test_safest_route_around_walls() {
+        // routes may go around the end of a wall, just outside the field
+        let input = parse_input(
+            r"
+        5,0 -> 5,8
+        5,3 -> 5,5",
+        );
+        let cover = build_cover(input.into_iter());
+        let route = cover
+            .safest_route((0, 4), (10, 4), Connectivity::Four, None)
+            .unwrap();
+        check_route(&cover, &route, (0, 4), (10, 4));
+        assert_eq!(route.exposure, 0);
+        assert_eq!(route.path.len(), 21);
+
+        // walled in on every side
+        let mut cover = Cover::sparse();
+        for l in [
+            line(0, 0, 4, 0),
+            line(0, 4, 4, 4),
+            line(0, 1, 0, 3),
+            line(4, 1, 4, 3),
+        ] {
+            cover.add_line(l);
+        }
+        assert!(cover
+            .safest_route((2, 2), (10, 10), Connectivity::Eight, Some(0))
+            .is_none());
+        let route = cover
+            .safest_route((2, 2), (10, 10), Connectivity::Eight, None)
+            .unwrap();
+        check_route(&cover, &route, (2, 2), (10, 10));
+        assert_eq!(route.exposure, 1);
+
+        // the start itself is too dangerous
+        assert!(cover
+            .safest_route((0, 0), (10, 10), Connectivity::Eight, Some(0))
+            .is_none());
+
+        // a far-off vent widens the bounding box without slowing nearby routes
+        cover.add_line(line(
+            1_000_000_000,
+            1_000_000_000,
+            1_000_000_000,
+            1_000_000_000,
+        ));
+        let route = cover
+            .safest_route((1, 1), (3, 3), Connectivity::Eight, Some(0))
+            .unwrap();
+        check_route(&cover, &route, (1, 1), (3, 3));
+        assert_eq!(route.path.len(), 3);
+
+        // nor an unreachable goal, from either side of the walls
+        assert!(cover
+            .safest_route((2, 2), (10, 10), Connectivity::Eight, Some(0))
+            .is_none());
+        assert!(cover
+            .safest_route((10, 10), (2, 2), Connectivity::Four, Some(0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_render_route() {
+        let cover = build_cover(parse_input(EXAMPLE).into_iter());
+        let route = cover
+            .safest_route((0, 1), (0, 8), Connectivity::Four, None)
+            .unwrap();
+        assert_eq!(route.exposure, 1);
+        assert_eq!(
+            cover.render_route(
+                &route,
+                Viewport {
+                    min_x: 0,
+                    min_y: 0,
+                    width: 4,
+                    height: 10
+                }
+            ),
+            "1.1.\n\
+             *111\n\
+             *.2.\n\
+             *..1\n\
+             *112\n\
+             *..1\n\
+             *.1.\n\
+             *1..\n\
+             !...\n\
+             2221\n"
+        );
+    }
+
+    /// Every point of a non-square cover is stored separately, and reads back
+    /// the coverage it was given.
+    #[test]
+    fn test_non_square_cover() {
+        for (width, height) in [(3, 7), (7, 3), (1, 5), (5, 1)] {
+            let mut cover = Cover::new(width, height);
+            for y in 0..height as i64 {
+                for x in 0..width as i64 {
+                    for _ in 0..(y * width as i64 + x + 1) {
+                        cover.cover(x, y);
+                    }
+                }
+            }
+
+            for y in 0..height as i64 {
+                for x in 0..width as i64 {
+                    assert_eq!(cover.count(x, y), (y * width as i64 + x + 1) as usize);
+                }
+            }
+
+            // storage is row-major
+            let counts = cover.counts.iter().cloned().collect::<Vec<_>>();
+            assert_eq!(counts, (1..=width * height).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_non_square_offset_cover() {
+        let mut cover = Cover::dense(-2, 10, 4, 2);
+        cover.add_line(line(-2, 10, 1, 10));
+        cover.add_line(line(1, 10, 1, 11));
+        assert_eq!(cover.to_string(), "1112\n...1\n");
+        assert_eq!(cover.count(1, 11), 1);
+        assert_eq!(cover.count(11, 1), 0);
+        assert_eq!(cover.count(-2, 11), 0);
+        assert_eq!(cover.count(2, 10), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the dense cover")]
+    fn test_cover_out_of_bounds() {
+        // (3, 1) would alias (0, 2) if the x coordinate were not bounds-checked
+        let mut cover = Cover::new(3, 5);
+        cover.cover(3, 1);
+    }
+
+    #[test]
+    fn test_build_non_square_cover() {
+        let input = parse_input(
+            r"
+        0,0 -> 2,0
+        1,0 -> 1,6
+        0,6 -> 2,4",
+        );
+
+        let cover = build_cover(input.into_iter());
+        assert!(cover.is_dense());
+        assert_eq!((cover.width, cover.height, cover.stride), (3, 7, 3));
+        assert_eq!(
+            cover.to_string(),
+            "121\n\
+             .1.\n\
+             .1.\n\
+             .1.\n\
+             .11\n\
+             .2.\n\
+             11.\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let lines = parse_lines("  0,9 -> 5,9\n\n-3, 4->3 ,-4\n").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], line(-3, 4, 3, -4));
+
+        let errors = [
+            ("0,9 5,9", LineErrorKind::MissingArrow),
+            ("0,9 -> 5,9 -> 6,9", LineErrorKind::ExtraArrow),
+            ("0,9,1 -> 5,9", LineErrorKind::MalformedPoint),
+            ("0 -> 5,9", LineErrorKind::MalformedPoint),
+            ("0,9 -> 5,", LineErrorKind::InvalidCoordinate),
+            ("0,a -> 5,9", LineErrorKind::InvalidCoordinate),
+        ];
+        for (text, kind) in errors.iter() {
+            let input = format!("0,0 -> 1,1\n\n{}\n2,2 -> 3,3", text);
+            assert_eq!(
+                parse_lines(&input),
+                Err(ParseLineError {
+                    line_number: 3,
+                    text: text.to_string(),
+                    kind: *kind,
+                })
+            );
+        }
+
+        let err = parse_lines("1,1 -> 2,2\n1,1 - 2,2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: expected `x1,y1 -> x2,y2`: \"1,1 - 2,2\""
+        );
+    }
+
+    #[test]
+    fn test_line_kind() {
+        assert_eq!(line(0, 9, 5, 9).kind(), LineKind::Horizontal);
+        assert_eq!(line(7, 0, 7, 4).kind(), LineKind::Vertical);
+        assert_eq!(line(8, 0, 0, 8).kind(), LineKind::Diagonal);
+        assert_eq!(line(0, 0, 8, 8).kind(), LineKind::Diagonal);
+        assert_eq!(line(0, 0, 4, 2).kind(), LineKind::Other);
+        assert_eq!(line(3, 3, 3, 3).kind(), LineKind::Horizontal);
+
+        let input = parse_input(&read_puzzle_input("day5-puzzle-input.txt"));
+        assert!(input.iter().all(|l| l.kind() != LineKind::Other));
+    }
 }
@@ -82,6 +82,9 @@
 //!
 //! After 256 days in the example above, there would be a total of 26984457539
 //! lanternfish!
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io::{self, Write};
 use std::str::FromStr;
 
 fn parse_input(input: &str) -> Vec<i32> {
@@ -109,59 +112,887 @@ impl Lanternfish for Vec<i32> {
     }
 }
 
+/// Parameters of an age-structured population, in which every individual has
+/// a timer that counts down by one each day.  When an individual's timer would
+/// pass below zero it instead resets, and a newborn joins the population.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct PopulationModel {
+    /// Timers take values in `0..timer_length`.
+    timer_length: usize,
+    /// The timer of an individual that has just spawned.
+    reset: usize,
+    /// The timer of a newborn.
+    newborn: usize,
+    /// The fraction of each timer group, as (numerator, denominator), that
+    /// dies at the start of each day, rounded down.  `None` if individuals
+    /// live forever.
+    mortality: Option<(usize, usize)>,
+}
+
+/// The lanternfish of the puzzle: a fish spawns every 7 days, and a newborn
+/// needs two more days for its first cycle.
+const LANTERNFISH: PopulationModel = PopulationModel {
+    timer_length: 9,
+    reset: 6,
+    newborn: 8,
+    mortality: None,
+};
+
+impl PopulationModel {
+    fn new(timer_length: usize, reset: usize, newborn: usize) -> Self {
+        assert!(reset < timer_length, "Reset timer is out of range");
+        assert!(newborn < timer_length, "Newborn timer is out of range");
+        Self {
+            timer_length,
+            reset,
+            newborn,
+            mortality: None,
+        }
+    }
+
+    /// The same model, with the given fraction of each timer group dying each
+    /// day.
+    fn with_mortality(self, numerator: usize, denominator: usize) -> Self {
+        assert!(denominator > 0, "Mortality must have a nonzero denominator");
+        assert!(numerator <= denominator, "Mortality must be at most 1");
+        Self {
+            mortality: Some((numerator, denominator)),
+            ..self
+        }
+    }
+}
+
+impl PopulationModel {
+    /// The factor by which the population eventually grows each day: the
+    /// dominant eigenvalue of the daily transition, found by power iteration.
+    /// Mortality is treated as exact, without rounding down.
+    fn growth_factor(&self) -> f64 {
+        let survival = match self.mortality {
+            Some((numerator, denominator)) => 1.0 - numerator as f64 / denominator as f64,
+            None => 1.0,
+        };
+        if survival == 0.0 {
+            return 0.0;
+        }
+
+        // iterate the lazy step (v + step(v)) / 2, which has the same dominant
+        // eigenvector but cannot oscillate when every cycle length shares a
+        // common factor.
+        let mut v = vec![1.0 / self.timer_length as f64; self.timer_length];
+        let mut lazy_factor = 1.0;
+        for _ in 0..10_000 {
+            let mut next = vec![0.0; self.timer_length];
+            for t in 1..self.timer_length {
+                next[t - 1] += v[t] * survival;
+            }
+            next[self.reset] += v[0] * survival;
+            next[self.newborn] += v[0] * survival;
+            next.iter_mut()
+                .zip(&v)
+                .for_each(|(n, v)| *n = (*n + v) / 2.0);
+
+            let total = next.iter().sum::<f64>();
+            next.iter_mut().for_each(|n| *n /= total);
+            let converged = (total - lazy_factor).abs() < 1e-15
+                && next.iter().zip(&v).all(|(n, v)| (n - v).abs() < 1e-15);
+            lazy_factor = total;
+            v = next;
+            if converged {
+                break;
+            }
+        }
+        2.0 * lazy_factor - 1.0
+    }
+}
+
+/// Error returned when a count no longer fits in its type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("population count overflowed")
+    }
+}
+
+/// A number of individuals.  Arithmetic reports overflow as an error rather
+/// than wrapping around.
+trait Count: Clone + Debug + PartialEq + Display {
+    /// Whether `survivors` can be computed.  Populations with mortality can
+    /// only be counted with types where it can.
+    const SUPPORTS_MORTALITY: bool = true;
+
+    fn zero() -> Self;
+
+    fn from_usize(n: usize) -> Self;
+
+    fn checked_add(&self, other: &Self) -> Result<Self, Overflow>;
+
+    fn checked_mul(&self, other: &Self) -> Result<Self, Overflow>;
+
+    /// The number left after the given fraction dies, rounding the deaths
+    /// down: `self - floor(self * numerator / denominator)`.
+    fn survivors(&self, numerator: usize, denominator: usize) -> Self;
+}
+
+macro_rules! impl_count_for_uint {
+    ($($t:ty),*) => {
+        $(
+            impl Count for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn from_usize(n: usize) -> Self {
+                    n as $t
+                }
+
+                fn checked_add(&self, other: &Self) -> Result<Self, Overflow> {
+                    <$t>::checked_add(*self, *other).ok_or(Overflow)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Result<Self, Overflow> {
+                    <$t>::checked_mul(*self, *other).ok_or(Overflow)
+                }
+
+                fn survivors(&self, numerator: usize, denominator: usize) -> Self {
+                    // split the division so that nothing overflows
+                    let (n, d) = (numerator as $t, denominator as $t);
+                    let deaths = (*self / d) * n + ((*self % d) as u128 * n as u128 / d as u128) as $t;
+                    *self - deaths
+                }
+            }
+        )*
+    };
+}
+
+impl_count_for_uint!(usize, u64, u128);
+
+/// An arbitrary-precision unsigned integer, which never overflows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BigUint {
+    /// Base 2^32 digits, least significant first, without trailing zeros.
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_u64(n: u64) -> Self {
+        let mut big = Self {
+            limbs: vec![n as u32, (n >> 32) as u32],
+        };
+        big.normalize();
+        big
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0_u64;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        let mut big = Self { limbs };
+        big.normalize();
+        big
+    }
+
+    /// `self - other`, where `other` must not be larger than `self`.
+    fn sub(&self, other: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0_i64;
+        for i in 0..self.limbs.len() {
+            let mut diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(diff as u32);
+        }
+        assert_eq!(borrow, 0, "Subtraction underflowed");
+        let mut big = Self { limbs };
+        big.normalize();
+        big
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut limbs = vec![0_u32; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0_u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        let mut big = Self { limbs };
+        big.normalize();
+        big
+    }
+
+    /// The quotient and remainder of division by a single word.
+    fn div_rem_small(&self, divisor: u64) -> (Self, u64) {
+        let mut limbs = vec![0_u32; self.limbs.len()];
+        let mut rem = 0_u128;
+        for i in (0..self.limbs.len()).rev() {
+            let cur = (rem << 32) | self.limbs[i] as u128;
+            limbs[i] = (cur / divisor as u128) as u32;
+            rem = cur % divisor as u128;
+        }
+        let mut big = Self { limbs };
+        big.normalize();
+        (big, rem as u64)
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.limbs.is_empty() {
+            return f.write_str("0");
+        }
+
+        // peel off base 10^9 digits, least significant first
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.limbs.is_empty() {
+            let (quotient, rem) = rest.div_rem_small(1_000_000_000);
+            chunks.push(rem);
+            rest = quotient;
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Count for BigUint {
+    fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    fn from_usize(n: usize) -> Self {
+        Self::from_u64(n as u64)
+    }
+
+    fn checked_add(&self, other: &Self) -> Result<Self, Overflow> {
+        Ok(self.add(other))
+    }
+
+    fn checked_mul(&self, other: &Self) -> Result<Self, Overflow> {
+        Ok(self.mul(other))
+    }
+
+    fn survivors(&self, numerator: usize, denominator: usize) -> Self {
+        let (deaths, _) = self
+            .mul(&Self::from_usize(numerator))
+            .div_rem_small(denominator as u64);
+        self.sub(&deaths)
+    }
+}
+
+/// A count modulo the prime (or any number) `P`.  Counts never overflow, but
+/// only their remainder is known.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Mod<const P: u64>(u64);
+
+impl<const P: u64> Display for Mod<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const P: u64> Count for Mod<P> {
+    // rounding down needs the actual count, not its remainder
+    const SUPPORTS_MORTALITY: bool = false;
+
+    fn zero() -> Self {
+        Mod(0)
+    }
+
+    fn from_usize(n: usize) -> Self {
+        Mod(n as u64 % P)
+    }
+
+    fn checked_add(&self, other: &Self) -> Result<Self, Overflow> {
+        Ok(Mod(((self.0 as u128 + other.0 as u128) % P as u128) as u64))
+    }
+
+    fn checked_mul(&self, other: &Self) -> Result<Self, Overflow> {
+        Ok(Mod((self.0 as u128 * other.0 as u128 % P as u128) as u64))
+    }
+
+    fn survivors(&self, _numerator: usize, _denominator: usize) -> Self {
+        unreachable!("Populations with mortality cannot be counted modulo {}", P)
+    }
+}
+
+/// A count that is an actual number of individuals, rather than a remainder,
+/// so that it can be compared and approximated.
+trait Magnitude: Count + PartialOrd {
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_magnitude_for_uint {
+    ($($t:ty),*) => {
+        $(
+            impl Magnitude for $t {
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_magnitude_for_uint!(usize, u64, u128);
+
+impl Magnitude for BigUint {
+    fn to_f64(&self) -> f64 {
+        self.limbs
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * 4294967296.0 + *limb as f64)
+    }
+}
+
+/// Counts the individuals of a population with each timer value.
+///
+/// Counts are stored in a ring buffer, so that counting down every timer is a
+/// rotation of the buffer's head rather than a shift of every count.
+#[derive(Clone, Debug)]
+struct Population<C = usize> {
+    model: PopulationModel,
+    /// The number of individuals with timer `t` is at `(head + t) % len`.
+    counts: Vec<C>,
+    head: usize,
+}
+
+impl<C: Count> Population<C> {
+    fn new(model: PopulationModel, timers: &[i32]) -> Self {
+        assert_supports_mortality::<C>(model);
+        let mut counts = vec![0; model.timer_length];
+        for t in timers {
+            assert!(
+                *t >= 0 && (*t as usize) < model.timer_length,
+                "Timer {} is out of range",
+                t
+            );
+            counts[*t as usize] += 1;
+        }
+        Self {
+            model,
+            counts: counts.into_iter().map(C::from_usize).collect(),
+            head: 0,
+        }
+    }
+
+    fn slot(&self, timer: usize) -> usize {
+        (self.head + timer) % self.counts.len()
+    }
+
+    /// The number of individuals with the given timer value.
+    fn count_at(&self, timer: usize) -> C {
+        self.counts[self.slot(timer)].clone()
+    }
+
+    /// The counts for each timer value, in order.
+    fn counts_by_timer(&self) -> Vec<C> {
+        (0..self.counts.len()).map(|t| self.count_at(t)).collect()
+    }
+
+    /// The total number of individuals.
+    fn count(&self) -> Result<C, Overflow> {
+        self.counts
+            .iter()
+            .try_fold(C::zero(), |sum, c| sum.checked_add(c))
+    }
+
+    /// Advances the population by one day.  On overflow, the population is
+    /// left unchanged.
+    fn step(&mut self) -> Result<(), Overflow> {
+        let mut counts = self.counts.clone();
+        if let Some((numerator, denominator)) = self.model.mortality {
+            counts
+                .iter_mut()
+                .for_each(|c| *c = c.survivors(numerator, denominator));
+        }
+
+        // the individuals with timer 0 spawn; every other timer counts down,
+        // which moves the old timer 0 slot to the end of the buffer.
+        let spawning = std::mem::replace(&mut counts[self.head], C::zero());
+        let head = self.slot(1);
+        let len = counts.len();
+        let reset = (head + self.model.reset) % len;
+        let newborn = (head + self.model.newborn) % len;
+        counts[reset] = counts[reset].checked_add(&spawning)?;
+        counts[newborn] = counts[newborn].checked_add(&spawning)?;
+
+        self.counts = counts;
+        self.head = head;
+        Ok(())
+    }
+
+    /// The same population, counted with a different type.
+    fn convert<D: Count>(&self, convert: impl Fn(&C) -> D) -> Population<D> {
+        assert_supports_mortality::<D>(self.model);
+        Population {
+            model: self.model,
+            counts: self.counts.iter().map(convert).collect(),
+            head: self.head,
+        }
+    }
+}
+
+fn assert_supports_mortality<C: Count>(model: PopulationModel) {
+    assert!(
+        model.mortality.is_none() || C::SUPPORTS_MORTALITY,
+        "Populations with mortality cannot be counted with {}",
+        std::any::type_name::<C>()
+    );
+}
+
+/// The linear map that advances a population by one or more days.
+///
+/// Entry (i, j) is the number of individuals with timer i that a single
+/// individual with timer j becomes, so multiplying the matrix by the vector of
+/// counts by timer advances the population.  Raising the matrix to the power n
+/// by repeated squaring advances it n days with O(log n) multiplications.
+#[derive(Clone, Debug, PartialEq)]
+struct TransitionMatrix<C> {
+    size: usize,
+    /// Entries in row-major order.
+    entries: Vec<C>,
+}
+
+impl<C: Count> TransitionMatrix<C> {
+    /// The matrix that advances a population of the given model by one day.
+    /// Mortality rounds down, so it cannot be represented by a matrix.
+    fn new(model: PopulationModel) -> Self {
+        assert!(
+            model.mortality.is_none(),
+            "Populations with mortality are not linear"
+        );
+
+        let size = model.timer_length;
+        let mut entries = vec![0; size * size];
+        for timer in 1..size {
+            entries[(timer - 1) * size + timer] += 1;
+        }
+        entries[model.reset * size] += 1;
+        entries[model.newborn * size] += 1;
+        Self {
+            size,
+            entries: entries.into_iter().map(C::from_usize).collect(),
+        }
+    }
+
+    fn identity(size: usize) -> Self {
+        let entries = (0..size * size)
+            .map(|i| C::from_usize((i / size == i % size) as usize))
+            .collect();
+        Self { size, entries }
+    }
+
+    fn entry(&self, row: usize, col: usize) -> &C {
+        &self.entries[row * self.size + col]
+    }
+
+    /// The product `self * other`.
+    fn mul(&self, other: &Self) -> Result<Self, Overflow> {
+        let mut entries = Vec::with_capacity(self.size * self.size);
+        for i in 0..self.size {
+            for j in 0..self.size {
+                let mut sum = C::zero();
+                for k in 0..self.size {
+                    sum = sum.checked_add(&self.entry(i, k).checked_mul(other.entry(k, j))?)?;
+                }
+                entries.push(sum);
+            }
+        }
+        Ok(Self {
+            size: self.size,
+            entries,
+        })
+    }
+
+    /// The matrix raised to the power `exp`.
+    fn pow(&self, mut exp: u64) -> Result<Self, Overflow> {
+        let mut result = Self::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// The product of the matrix and the column vector `v`.
+    fn apply(&self, v: &[C]) -> Result<Vec<C>, Overflow> {
+        (0..self.size)
+            .map(|i| {
+                (0..self.size).try_fold(C::zero(), |sum, j| {
+                    sum.checked_add(&self.entry(i, j).checked_mul(&v[j])?)
+                })
+            })
+            .collect()
+    }
+}
+
+impl<C: Count> Population<C> {
+    /// The counts by timer after `days` more days, computed in O(log days)
+    /// matrix multiplications.  Mortality rounds down and has no matrix, so
+    /// with mortality the population is stepped one day at a time instead,
+    /// stopping early once it no longer changes.
+    fn projected_counts(&self, days: u64) -> Result<Vec<C>, Overflow> {
+        if self.model.mortality.is_some() {
+            let mut population = self.clone();
+            for _ in 0..days {
+                let before = population.counts_by_timer();
+                population.step()?;
+                if population.counts_by_timer() == before {
+                    break;
+                }
+            }
+            return Ok(population.counts_by_timer());
+        }
+
+        TransitionMatrix::new(self.model)
+            .pow(days)?
+            .apply(&self.counts_by_timer())
+    }
+
+    /// The total number of individuals after `days` more days.
+    fn projected_count(&self, days: u64) -> Result<C, Overflow> {
+        self.projected_counts(days)?
+            .iter()
+            .try_fold(C::zero(), |sum, c| sum.checked_add(c))
+    }
+}
+
 #[derive(Debug)]
-struct LanternfishSchool {
-    num_0s: usize,
-    num_1s: usize,
-    num_2s: usize,
-    num_3s: usize,
-    num_4s: usize,
-    num_5s: usize,
-    num_6s: usize,
-    num_7s: usize,
-    num_8s: usize,
+struct LanternfishSchool<C = usize> {
+    population: Population<C>,
 }
 
 impl LanternfishSchool {
     fn init(nums: &[i32]) -> Self {
+        Self::with_count_type(nums)
+    }
+}
+
+impl<C: Count> LanternfishSchool<C> {
+    /// A school counted with `C`, e.g. `u128` or [`BigUint`] for very large
+    /// schools, or [`Mod`] to keep only the remainder.
+    fn with_count_type(nums: &[i32]) -> Self {
+        Self {
+            population: Population::new(LANTERNFISH, nums),
+        }
+    }
+
+    /// The number of fish.  Panics if it overflows; see [`Self::try_count`].
+    fn count(&self) -> C {
+        self.try_count().expect("Lanternfish count overflowed")
+    }
+
+    fn try_count(&self) -> Result<C, Overflow> {
+        self.population.count()
+    }
+
+    /// Advances the school by one day, failing if the count of any timer
+    /// overflows.
+    fn try_progress(&mut self) -> Result<(), Overflow> {
+        self.population.step()
+    }
+}
+
+impl<C: Count> Lanternfish for LanternfishSchool<C> {
+    fn progress(&mut self) {
+        self.try_progress().expect("Lanternfish count overflowed");
+    }
+}
+
+/// The counts by timer of a population, recorded once per day.
+#[derive(Clone, Debug, PartialEq)]
+struct TimeSeries<C> {
+    /// `days[d][t]` is the number of individuals with timer `t` on day `d`,
+    /// where day 0 is the initial population.
+    days: Vec<Vec<C>>,
+}
+
+impl<C: Count> Population<C> {
+    /// Records the population today and on each of the next `days` days,
+    /// leaving the population on the last day.
+    fn record(&mut self, days: usize) -> Result<TimeSeries<C>, Overflow> {
+        let mut series = vec![self.counts_by_timer()];
+        for _ in 0..days {
+            self.step()?;
+            series.push(self.counts_by_timer());
+        }
+        Ok(TimeSeries { days: series })
+    }
+}
+
+impl<C: Count> TimeSeries<C> {
+    /// The number of recorded days, including day 0.
+    fn len(&self) -> usize {
+        self.days.len()
+    }
+
+    /// The total number of individuals on each day.
+    fn totals(&self) -> Result<Vec<C>, Overflow> {
+        self.days
+            .iter()
+            .map(|counts| {
+                counts
+                    .iter()
+                    .try_fold(C::zero(), |sum, c| sum.checked_add(c))
+            })
+            .collect()
+    }
+
+    /// Writes one row per day: the day, the total and the count for each
+    /// timer value.
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let totals = self
+            .totals()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let timers = self.days.first().map_or(0, |counts| counts.len());
+
+        write!(out, "day,total")?;
+        for t in 0..timers {
+            write!(out, ",t{}", t)?;
+        }
+        writeln!(out)?;
+        for (day, (counts, total)) in self.days.iter().zip(&totals).enumerate() {
+            write!(out, "{},{}", day, total)?;
+            for c in counts {
+                write!(out, ",{}", c)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the series as a JSON array with one object per day, e.g.
+    /// `[{"day":0,"total":5,"counts":[0,1,1,2,1,0,0,0,0]}]`.  Counts are
+    /// written as plain integers, however large.
+    fn write_json(&self, out: &mut impl Write) -> io::Result<()> {
+        let totals = self
+            .totals()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        write!(out, "[")?;
+        for (day, (counts, total)) in self.days.iter().zip(&totals).enumerate() {
+            if day > 0 {
+                write!(out, ",")?;
+            }
+            write!(out, "{{\"day\":{},\"total\":{},\"counts\":[", day, total)?;
+            for (t, c) in counts.iter().enumerate() {
+                if t > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "{}", c)?;
+            }
+            write!(out, "]}}")?;
+        }
+        writeln!(out, "]")
+    }
+}
+
+impl<C: Magnitude> TimeSeries<C> {
+    /// The ratio of each day's total to the previous day's, starting with
+    /// day 1.  `None` after a day with no individuals.
+    fn growth_rates(&self) -> Result<Vec<Option<f64>>, Overflow> {
+        let totals = self.totals()?;
+        Ok(totals
+            .windows(2)
+            .map(|w| match w[0].to_f64() {
+                prev if prev > 0.0 => Some(w[1].to_f64() / prev),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// The first recorded day on which the total exceeds `threshold`.
+    fn first_day_exceeding(&self, threshold: &C) -> Result<Option<usize>, Overflow> {
+        Ok(self.totals()?.iter().position(|total| total > threshold))
+    }
+}
+
+/// A single tracked individual.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Fish {
+    timer: usize,
+    /// The individual that spawned this one, or `None` for the initial
+    /// population.
+    parent: Option<usize>,
+    /// 0 for the initial population, otherwise one more than the parent's.
+    generation: usize,
+    /// The day on which the individual was born; 0 for the initial population.
+    born: usize,
+}
+
+/// A population in which every individual is tracked by ID, recording who
+/// spawned whom.  Memory grows with the number of individuals, so this is
+/// only suitable for small populations; [`Population`] counts large ones.
+///
+/// IDs are assigned in order of birth, starting with the initial population
+/// in input order.  Individuals born on the same day are numbered in the
+/// order of their parents' IDs.
+#[derive(Clone, Debug)]
+struct Lineage {
+    model: PopulationModel,
+    /// Indexed by ID.
+    fish: Vec<Fish>,
+    /// The IDs of each individual's children, in order of birth.
+    children: Vec<Vec<usize>>,
+    day: usize,
+}
+
+impl Lineage {
+    fn new(model: PopulationModel, timers: &[i32]) -> Self {
+        assert!(
+            model.mortality.is_none(),
+            "Lineages cannot be tracked with mortality"
+        );
+        let fish = timers
+            .iter()
+            .map(|t| {
+                assert!(
+                    *t >= 0 && (*t as usize) < model.timer_length,
+                    "Timer {} is out of range",
+                    t
+                );
+                Fish {
+                    timer: *t as usize,
+                    parent: None,
+                    generation: 0,
+                    born: 0,
+                }
+            })
+            .collect::<Vec<_>>();
         Self {
-            num_0s: nums.iter().cloned().filter(|n| *n == 0).count(),
-            num_1s: nums.iter().cloned().filter(|n| *n == 1).count(),
-            num_2s: nums.iter().cloned().filter(|n| *n == 2).count(),
-            num_3s: nums.iter().cloned().filter(|n| *n == 3).count(),
-            num_4s: nums.iter().cloned().filter(|n| *n == 4).count(),
-            num_5s: nums.iter().cloned().filter(|n| *n == 5).count(),
-            num_6s: nums.iter().cloned().filter(|n| *n == 6).count(),
-            num_7s: nums.iter().cloned().filter(|n| *n == 7).count(),
-            num_8s: nums.iter().cloned().filter(|n| *n == 8).count(),
+            model,
+            children: vec![Vec::new(); fish.len()],
+            fish,
+            day: 0,
+        }
+    }
+
+    fn init(timers: &[i32]) -> Self {
+        Self::new(LANTERNFISH, timers)
+    }
+
+    fn len(&self) -> usize {
+        self.fish.len()
+    }
+
+    fn fish(&self, id: usize) -> &Fish {
+        &self.fish[id]
+    }
+
+    fn children(&self, id: usize) -> &[usize] {
+        &self.children[id]
+    }
+
+    /// The timers of every individual, in order of ID.
+    fn timers(&self) -> Vec<i32> {
+        self.fish.iter().map(|f| f.timer as i32).collect()
+    }
+
+    /// The IDs of every descendant of `id` so far, in order of ID.
+    fn descendants(&self, id: usize) -> Vec<usize> {
+        let mut descendants = Vec::new();
+        let mut stack = self.children[id].clone();
+        while let Some(child) = stack.pop() {
+            descendants.push(child);
+            stack.extend_from_slice(&self.children[child]);
         }
+        descendants.sort_unstable();
+        descendants
     }
 
-    fn count(&self) -> usize {
-        self.num_0s
-            + self.num_1s
-            + self.num_2s
-            + self.num_3s
-            + self.num_4s
-            + self.num_5s
-            + self.num_6s
-            + self.num_7s
-            + self.num_8s
+    /// The descendants of `id` alive `days` days from now, which is all of
+    /// them since individuals never die.  The lineage itself is left as it
+    /// is; the IDs are those the lineage will give once advanced by `days`.
+    fn descendants_after(&self, id: usize, days: usize) -> Vec<usize> {
+        let mut future = self.clone();
+        for _ in 0..days {
+            future.progress();
+        }
+        future.descendants(id)
+    }
+
+    /// The number of individuals in each generation, starting with the
+    /// initial population.
+    fn generation_histogram(&self) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        for f in &self.fish {
+            if histogram.len() <= f.generation {
+                histogram.resize(f.generation + 1, 0);
+            }
+            histogram[f.generation] += 1;
+        }
+        histogram
     }
 }
 
-impl Lanternfish for LanternfishSchool {
+impl Lanternfish for Lineage {
     fn progress(&mut self) {
-        let init_num_0s = self.num_0s;
-        self.num_0s = self.num_1s;
-        self.num_1s = self.num_2s;
-        self.num_2s = self.num_3s;
-        self.num_3s = self.num_4s;
-        self.num_4s = self.num_5s;
-        self.num_5s = self.num_6s;
-        self.num_6s = self.num_7s + init_num_0s;
-        self.num_7s = self.num_8s;
-        self.num_8s = init_num_0s;
+        self.day += 1;
+        let existing = self.fish.len();
+        for id in 0..existing {
+            let parent = &mut self.fish[id];
+            if parent.timer > 0 {
+                parent.timer -= 1;
+                continue;
+            }
+
+            parent.timer = self.model.reset;
+            let child = Fish {
+                timer: self.model.newborn,
+                parent: Some(id),
+                generation: parent.generation + 1,
+                born: self.day,
+            };
+            self.children[id].push(self.fish.len());
+            self.children.push(Vec::new());
+            self.fish.push(child);
+        }
     }
 }
 
@@ -210,4 +1041,414 @@ mod tests {
             println!("{:?}", school);
         }
     }
+
+    #[test]
+    fn test_school_matches_individuals() {
+        let mut fish = vec![3, 4, 3, 1, 2];
+        let mut school = LanternfishSchool::init(&fish);
+        for _ in 1..=18 {
+            fish.progress();
+            school.progress();
+            for t in 0..9 {
+                let expected = fish.iter().filter(|f| **f == t as i32).count();
+                assert_eq!(school.population.count_at(t), expected);
+            }
+        }
+        assert_eq!(school.count(), 26);
+
+        for _ in 19..=80 {
+            school.progress();
+        }
+        assert_eq!(school.count(), 5934);
+
+        for _ in 81..=256 {
+            school.progress();
+        }
+        assert_eq!(school.count(), 26984457539);
+    }
+
+    #[test]
+    fn test_custom_population_model() {
+        // spawns every 3 days, newborns take one more day
+        let model = PopulationModel::new(4, 2, 3);
+        let mut population = Population::<usize>::new(model, &[0]);
+        let totals = (0..8)
+            .map(|_| {
+                population.step().unwrap();
+                population.count().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(totals, vec![2, 2, 2, 3, 4, 4, 5, 7]);
+
+        // newborns that start on the reset timer
+        let model = PopulationModel::new(3, 2, 2);
+        let mut population = Population::<usize>::new(model, &[0]);
+        for day in 1..=9 {
+            population.step().unwrap();
+            assert_eq!(population.count(), Ok(1 << ((day + 2) / 3)));
+        }
+    }
+
+    #[test]
+    fn test_mortality() {
+        let model = LANTERNFISH.with_mortality(1, 2);
+        let mut population = Population::<usize>::new(model, &[0; 10]);
+        population.step().unwrap();
+        assert_eq!(population.count_at(6), 5);
+        assert_eq!(population.count_at(8), 5);
+        population.step().unwrap();
+        assert_eq!(population.count_at(5), 3);
+        assert_eq!(population.count_at(7), 3);
+        assert_eq!(population.count(), Ok(6));
+
+        let model = LANTERNFISH.with_mortality(1, 1);
+        let mut population = Population::<usize>::new(model, &[3, 4, 3, 1, 2]);
+        population.step().unwrap();
+        assert_eq!(population.count(), Ok(0));
+
+        // big counts round the deaths down the same way
+        let model = LANTERNFISH.with_mortality(2, 7);
+        let mut small = Population::<u64>::new(model, &[3, 4, 3, 1, 2]);
+        let mut big = Population::<BigUint>::new(model, &[3, 4, 3, 1, 2]);
+        for _ in 0..200 {
+            small.step().unwrap();
+            big.step().unwrap();
+            assert_eq!(
+                small.count().unwrap().to_string(),
+                big.count().unwrap().to_string()
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Mortality must have a nonzero denominator")]
+    fn test_mortality_zero_denominator() {
+        LANTERNFISH.with_mortality(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Populations with mortality cannot be counted with")]
+    fn test_mortality_modulo() {
+        let model = LANTERNFISH.with_mortality(1, 2);
+        Population::<Mod<1_000_000_007>>::new(model, &[3, 4, 3, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Populations with mortality cannot be counted with")]
+    fn test_mortality_convert_modulo() {
+        let model = LANTERNFISH.with_mortality(1, 2);
+        let population = Population::<u64>::new(model, &[3, 4, 3, 1, 2]);
+        population.convert(|&c| Mod::<1_000_000_007>(c % 1_000_000_007));
+    }
+
+    #[test]
+    fn test_projection_matches_simulation() {
+        let mut school = LanternfishSchool::<u128>::with_count_type(&[3, 4, 3, 1, 2]);
+        let start = school.population.clone();
+        for day in 0..=300 {
+            let projected = start.projected_counts(day).unwrap();
+            assert_eq!(
+                projected,
+                school.population.counts_by_timer(),
+                "day {}",
+                day
+            );
+            school.progress();
+        }
+
+        let model = PopulationModel::new(4, 2, 3);
+        let mut population = Population::<usize>::new(model, &[0, 1, 3, 3]);
+        let start = population.clone();
+        for day in 0..=50 {
+            assert_eq!(start.projected_count(day), population.count());
+            population.step().unwrap();
+        }
+
+        // mortality has no matrix, but can still be projected
+        let model = LANTERNFISH.with_mortality(2, 7);
+        let mut population = Population::<u64>::new(model, &[3, 4, 3, 1, 2]);
+        let start = population.clone();
+        for day in 0..=200 {
+            assert_eq!(
+                start.projected_counts(day),
+                Ok(population.counts_by_timer())
+            );
+            population.step().unwrap();
+        }
+
+        // and a population that dies out stays that way
+        let model = LANTERNFISH.with_mortality(1, 1);
+        let population = Population::<u64>::new(model, &[3, 4, 3, 1, 2]);
+        assert_eq!(population.projected_count(1_000_000_000_000), Ok(0));
+    }
+
+    #[test]
+    fn test_projection_puzzle() {
+        let input = parse_input(&read_puzzle_input("day6-puzzle-input.txt"));
+        let school = LanternfishSchool::init(&input);
+        let population = &school.population;
+        assert_eq!(population.projected_count(80), Ok(351188));
+        assert_eq!(population.projected_count(256), Ok(1595779846729));
+    }
+
+    #[test]
+    fn test_projection_overflow() {
+        let school = LanternfishSchool::<u128>::with_count_type(&[3, 4, 3, 1, 2]);
+        let population = &school.population;
+
+        // the population grows by a factor of about 1.09 per day, so it
+        // outgrows a u128 after roughly a thousand days.
+        assert!(population.projected_count(900).is_ok());
+        assert_eq!(population.projected_count(1100), Err(Overflow));
+        assert_eq!(population.projected_count(1_000_000_000_000), Err(Overflow));
+    }
+
+    #[test]
+    fn test_projection_modulo() {
+        const P: u64 = 1_000_000_007;
+        let school = LanternfishSchool::<u128>::with_count_type(&[3, 4, 3, 1, 2]);
+        let exact = school.population.projected_count(900).unwrap();
+        let population = school
+            .population
+            .convert(|c| Mod::<P>::from_usize(*c as usize));
+        assert_eq!(
+            population.projected_count(900),
+            Ok(Mod((exact % P as u128) as u64))
+        );
+
+        // 10^12 days = 2 * (5 * 10^11) days
+        let half = population.projected_counts(500_000_000_000).unwrap();
+        let matrix = TransitionMatrix::<Mod<P>>::new(LANTERNFISH)
+            .pow(500_000_000_000)
+            .unwrap();
+        let full = matrix.apply(&half).unwrap();
+        assert_eq!(population.projected_counts(1_000_000_000_000), Ok(full));
+    }
+
+    #[test]
+    fn test_count_types_agree() {
+        let input = parse_input(&read_puzzle_input("day6-puzzle-input.txt"));
+        let mut small = LanternfishSchool::<u64>::with_count_type(&input);
+        let mut wide = LanternfishSchool::<u128>::with_count_type(&input);
+        let mut big = LanternfishSchool::<BigUint>::with_count_type(&input);
+        let mut modular = LanternfishSchool::<Mod<1_000_000_007>>::with_count_type(&input);
+        for day in 1..=256 {
+            small.progress();
+            wide.progress();
+            big.progress();
+            modular.progress();
+            let count = small.count();
+            assert_eq!(wide.count(), count as u128, "day {}", day);
+            assert_eq!(big.count().to_string(), count.to_string(), "day {}", day);
+            assert_eq!(modular.count(), Mod(count % 1_000_000_007), "day {}", day);
+        }
+        assert_eq!(big.count().to_string(), "1595779846729");
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let mut school = LanternfishSchool::<u64>::with_count_type(&[3, 4, 3, 1, 2]);
+        let mut last_day = 0;
+        while school.try_progress().is_ok() {
+            last_day += 1;
+        }
+        assert!(
+            last_day > 500 && last_day < 520,
+            "overflowed on day {}",
+            last_day
+        );
+
+        // the school is left as it was before the overflowing day
+        let mut exact = LanternfishSchool::<u128>::with_count_type(&[3, 4, 3, 1, 2]);
+        for _ in 0..last_day {
+            exact.progress();
+        }
+        assert_eq!(
+            school.population.counts_by_timer(),
+            exact
+                .population
+                .counts_by_timer()
+                .into_iter()
+                .map(|c| c as u64)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_big_school() {
+        let mut school = LanternfishSchool::<BigUint>::with_count_type(&[3, 4, 3, 1, 2]);
+        let start = school.population.clone();
+        for _ in 0..10_000 {
+            school.progress();
+        }
+        let count = school.count().to_string();
+        // growth of about 1.09 per day gives 10_000 * log10(1.09) digits
+        assert!(
+            count.len() > 370 && count.len() < 390,
+            "{} digits",
+            count.len()
+        );
+        assert_eq!(start.projected_count(10_000).unwrap().to_string(), count);
+    }
+
+    #[test]
+    fn test_time_series_csv() {
+        let mut population = Population::<usize>::new(LANTERNFISH, &[3, 4, 3, 1, 2]);
+        let series = population.record(2).unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.totals(), Ok(vec![5, 5, 6]));
+
+        let mut csv = Vec::new();
+        series.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "day,total,t0,t1,t2,t3,t4,t5,t6,t7,t8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n\
+             2,6,1,2,1,0,0,0,1,0,1\n"
+        );
+    }
+
+    #[test]
+    fn test_time_series_json() {
+        let mut population = Population::<BigUint>::new(LANTERNFISH, &[3, 4, 3, 1, 2]);
+        let series = population.record(1).unwrap();
+        let mut json = Vec::new();
+        series.write_json(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[{\"day\":0,\"total\":5,\"counts\":[0,1,1,2,1,0,0,0,0]},\
+             {\"day\":1,\"total\":5,\"counts\":[1,1,2,1,0,0,0,0,0]}]\n"
+        );
+    }
+
+    #[test]
+    fn test_time_series_matches_school() {
+        let input = parse_input(&read_puzzle_input("day6-puzzle-input.txt"));
+        let mut population = Population::<u64>::new(LANTERNFISH, &input);
+        let series = population.record(256).unwrap();
+        let totals = series.totals().unwrap();
+        assert_eq!(totals[80], 351188);
+        assert_eq!(totals[256], 1595779846729);
+        assert_eq!(population.count(), Ok(1595779846729));
+
+        let day = series.first_day_exceeding(&1_000_000).unwrap().unwrap();
+        assert!(totals[day - 1] <= 1_000_000 && totals[day] > 1_000_000);
+        assert_eq!(series.first_day_exceeding(&1595779846729), Ok(None));
+
+        let mut school = LanternfishSchool::<u64>::with_count_type(&[3, 4, 3, 1, 2]);
+        let series = school.population.record(80).unwrap();
+        assert_eq!(series.first_day_exceeding(&25), Ok(Some(18)));
+        assert_eq!(series.first_day_exceeding(&5933), Ok(Some(80)));
+    }
+
+    #[test]
+    fn test_growth_factor() {
+        // lanternfish counts follow n(d) = n(d - 7) + n(d - 9)
+        let lambda = LANTERNFISH.growth_factor();
+        assert!(
+            (lambda.powi(9) - lambda.powi(2) - 1.0).abs() < 1e-9,
+            "{}",
+            lambda
+        );
+
+        // the day-over-day growth rate converges to it, slowly
+        let mut population = Population::<BigUint>::new(LANTERNFISH, &[3, 4, 3, 1, 2]);
+        let rates = population.record(3000).unwrap().growth_rates().unwrap();
+        assert_eq!(rates.len(), 3000);
+        assert!((rates[2999].unwrap() - lambda).abs() < 1e-9);
+
+        // every individual doubles every three days
+        let model = PopulationModel::new(3, 2, 2);
+        assert!((model.growth_factor() - 2_f64.cbrt()).abs() < 1e-9);
+
+        let model = LANTERNFISH.with_mortality(1, 4);
+        assert!((model.growth_factor() - 0.75 * lambda).abs() < 1e-9);
+        assert_eq!(LANTERNFISH.with_mortality(1, 1).growth_factor(), 0.0);
+
+        let mut population = Population::<usize>::new(LANTERNFISH.with_mortality(1, 1), &[1]);
+        let rates = population.record(2).unwrap().growth_rates().unwrap();
+        assert_eq!(rates, vec![Some(0.0), None]);
+    }
+
+    #[test]
+    fn test_lineage_matches_individuals() {
+        let mut fish = vec![3, 4, 3, 1, 2];
+        let mut lineage = Lineage::init(&fish);
+        for _ in 1..=18 {
+            fish.progress();
+            lineage.progress();
+            assert_eq!(lineage.timers(), fish);
+        }
+        assert_eq!(lineage.len(), 26);
+
+        let input = parse_input(&read_puzzle_input("day6-puzzle-input.txt"));
+        let mut lineage = Lineage::init(&input);
+        for _ in 0..80 {
+            lineage.progress();
+        }
+        assert_eq!(lineage.len(), 351188);
+    }
+
+    #[test]
+    fn test_lineage_queries() {
+        let mut lineage = Lineage::init(&[3, 4, 3, 1, 2]);
+
+        // fish 3 spawns on days 2, 9 and 16, its first child on days 11 and
+        // 18, and its second child on day 18.
+        let descendants = lineage.descendants_after(3, 18);
+        assert_eq!(lineage.len(), 5);
+        assert!(lineage.descendants(3).is_empty());
+        for _ in 0..18 {
+            lineage.progress();
+        }
+        assert_eq!(lineage.descendants(3), descendants);
+        assert_eq!(lineage.children(3), &[5, 10, 20]);
+        assert_eq!(descendants.len(), 6);
+        assert_eq!(
+            descendants
+                .iter()
+                .map(|id| lineage.fish(*id).born)
+                .collect::<Vec<_>>(),
+            vec![2, 9, 11, 16, 18, 18]
+        );
+        let grandchild = descendants[2];
+        assert_eq!(lineage.fish(grandchild).parent, Some(5));
+        assert_eq!(lineage.fish(grandchild).generation, 2);
+        assert_eq!(lineage.fish(5).parent, Some(3));
+        assert_eq!(lineage.fish(3).parent, None);
+
+        // every fish descends from exactly one of the initial fish
+        let total = (0..5)
+            .map(|id| lineage.descendants(id).len())
+            .sum::<usize>();
+        assert_eq!(total + 5, lineage.len());
+
+        let histogram = lineage.generation_histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), 26);
+        assert_eq!(histogram[0], 5);
+        assert_eq!(histogram.len(), 3);
+    }
+
+    #[test]
+    fn test_lineage_generations_match_counts() {
+        let model = PopulationModel::new(4, 2, 3);
+        let mut lineage = Lineage::new(model, &[0, 1, 3, 3]);
+        let mut population = Population::<usize>::new(model, &[0, 1, 3, 3]);
+        for _ in 0..30 {
+            lineage.progress();
+            population.step().unwrap();
+            assert_eq!(Ok(lineage.len()), population.count());
+            for t in 0..4 {
+                let tracked = lineage.fish.iter().filter(|f| f.timer == t).count();
+                assert_eq!(tracked, population.count_at(t));
+            }
+        }
+        for f in &lineage.fish {
+            if let Some(parent) = f.parent {
+                assert_eq!(f.generation, lineage.fish(parent).generation + 1);
+                assert!(f.born > lineage.fish(parent).born);
+            }
+        }
+    }
 }
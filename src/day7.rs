@@ -85,6 +85,7 @@
 //!
 //! Your puzzle answer was 96798233.
 
+use std::convert::TryInto;
 use std::str::FromStr;
 
 fn parse_input(input: &str) -> Vec<i32> {
@@ -96,16 +97,16 @@ fn parse_input(input: &str) -> Vec<i32> {
 
 /// Computes the minimum cost to align the crabs' horizontal positions, assuming
 /// each step burns a single unit of fuel.
-fn align_cheapest(horiz_pos: &[i32]) -> i32 {
-    let min = *horiz_pos.iter().min().unwrap();
-    let max = *horiz_pos.iter().max().unwrap();
+fn align_cheapest(horiz_pos: &[i32]) -> i64 {
+    let min = *horiz_pos.iter().min().unwrap() as i64;
+    let max = *horiz_pos.iter().max().unwrap() as i64;
 
     (min..=max)
         .map(|pos| {
             horiz_pos
                 .iter()
-                .map(|start| (pos - start).abs())
-                .sum::<i32>()
+                .map(|start| (pos - *start as i64).abs())
+                .sum::<i64>()
         })
         .min()
         .unwrap()
@@ -114,26 +115,668 @@ fn align_cheapest(horiz_pos: &[i32]) -> i32 {
 /// Computes the minimum cost to align the crabs' horizontal positions, assuming
 /// each step burns a one more unit of fuel than the previous step, starting at
 /// 1 unit of fuel for the first step.
-fn align_cheapest_2(horiz_pos: &[i32]) -> i32 {
-    let min = *horiz_pos.iter().min().unwrap();
-    let max = *horiz_pos.iter().max().unwrap();
+fn align_cheapest_2(horiz_pos: &[i32]) -> i64 {
+    let min = *horiz_pos.iter().min().unwrap() as i64;
+    let max = *horiz_pos.iter().max().unwrap() as i64;
 
     (min..=max)
         .map(|pos| {
             horiz_pos
                 .iter()
-                .map(|start| ((pos - start).abs() * ((pos - start).abs() + 1)) / 2)
-                .sum::<i32>()
+                .map(|start| {
+                    let d = (pos - *start as i64).abs();
+                    d * (d + 1) / 2
+                })
+                .sum::<i64>()
         })
         .min()
         .unwrap()
 }
 
+/// Computes the same cost as [`align_cheapest`] in O(n log n).
+///
+/// The total linear cost `sum |p - x|` only decreases while more crabs lie
+/// to the right of `p` than to the left, so it is minimized at a median.
+fn align_median(horiz_pos: &[i32]) -> i64 {
+    let mut sorted = horiz_pos.to_vec();
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2] as i64;
+
+    sorted
+        .iter()
+        .map(|start| (median - *start as i64).abs())
+        .sum()
+}
+
+/// Computes the same cost as [`align_cheapest_2`] in O(n).
+///
+/// The triangular cost of a move of `d` is `(d^2 + |d|) / 2`, so over the
+/// reals the total cost has derivative `sum (p - x) + sum sign(p - x) / 2`,
+/// which is `n (p - mean)` give or take `n / 2`.  The derivative is therefore
+/// negative below `mean - 1/2` and positive above `mean + 1/2`, and since the
+/// cost is convex its integer minimum is within that interval, rounded out to
+/// integers.  Only those few positions are tried.
+fn align_mean(horiz_pos: &[i32]) -> i64 {
+    let n = horiz_pos.len() as i64;
+    let sum = horiz_pos.iter().map(|x| *x as i64).sum::<i64>();
+    // floor(mean - 1/2) and ceil(mean + 1/2)
+    let lo = (2 * sum - n).div_euclid(2 * n);
+    let hi = -(-(2 * sum + n)).div_euclid(2 * n);
+
+    (lo..=hi)
+        .map(|pos| {
+            horiz_pos
+                .iter()
+                .map(|start| {
+                    let d = (pos - *start as i64).abs();
+                    d * (d + 1) / 2
+                })
+                .sum::<i64>()
+        })
+        .min()
+        .unwrap()
+}
+
+/// The fuel a crab burns to move a given distance.
+trait FuelCost {
+    /// The fuel to move `distance >= 0` steps.
+    fn cost(&self, distance: i64) -> i64;
+
+    /// Whether the cost is convex and nondecreasing in the distance, which
+    /// makes the total cost of aligning at a position convex in the position.
+    fn is_convex(&self) -> bool {
+        false
+    }
+}
+
+/// Each step burns one unit of fuel, as in part one.
+#[derive(Copy, Clone, Debug)]
+struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: i64) -> i64 {
+        distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Each step burns one more unit than the previous step, as in part two.
+///
+/// The product is taken in `i128`, so any distance whose cost fits in an `i64`
+/// (up to a little over 4.29e9) is handled; larger distances panic.
+#[derive(Copy, Clone, Debug)]
+struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: i64) -> i64 {
+        let distance = distance as i128;
+        (distance * (distance + 1) / 2)
+            .try_into()
+            .expect("Triangular fuel cost overflows i64")
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// The fuel is the square of the distance.
+///
+/// As with [`Triangular`], the square is taken in `i128` and panics if it
+/// does not fit in an `i64`.
+#[derive(Copy, Clone, Debug)]
+struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: i64) -> i64 {
+        let distance = distance as i128;
+        (distance * distance)
+            .try_into()
+            .expect("Quadratic fuel cost overflows i64")
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Another cost, but never more than `cap`.  Capping makes a cost concave
+/// beyond the cap, so it is never treated as convex.
+#[derive(Copy, Clone, Debug)]
+struct Capped<C> {
+    cost: C,
+    cap: i64,
+}
+
+impl<C: FuelCost> FuelCost for Capped<C> {
+    fn cost(&self, distance: i64) -> i64 {
+        self.cost.cost(distance).min(self.cap)
+    }
+}
+
+/// A user-supplied cost that the caller promises is convex and
+/// nondecreasing.  Plain closures are also costs, but are never assumed to be
+/// convex.
+#[derive(Copy, Clone, Debug)]
+struct Convex<F>(F);
+
+impl<F: Fn(i64) -> i64> FuelCost for Convex<F> {
+    fn cost(&self, distance: i64) -> i64 {
+        self.0(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+impl<F: Fn(i64) -> i64> FuelCost for F {
+    fn cost(&self, distance: i64) -> i64 {
+        self(distance)
+    }
+}
+
+/// The total fuel for every crab to move to `pos`.
+fn total_cost(horiz_pos: &[i32], pos: i64, cost: &impl FuelCost) -> i64 {
+    weighted_total_cost(&Crab::unweighted(horiz_pos), pos, cost)
+        .try_into()
+        .expect("Total fuel cost overflows i64")
+}
+
+/// A crab whose fuel use is multiplied by `weight`, e.g. because its
+/// submarine is less efficient.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Crab {
+    pos: i32,
+    weight: i64,
+}
+
+impl Crab {
+    fn unweighted(horiz_pos: &[i32]) -> Vec<Crab> {
+        horiz_pos
+            .iter()
+            .map(|pos| Crab {
+                pos: *pos,
+                weight: 1,
+            })
+            .collect()
+    }
+}
+
+/// The total weighted fuel for every crab to move to `pos`.  The sum is taken
+/// in `i128`, so that positions far from the cheapest can still be compared
+/// when their fuel does not fit in an `i64`.
+fn weighted_total_cost(crabs: &[Crab], pos: i64, cost: &impl FuelCost) -> i128 {
+    crabs
+        .iter()
+        .map(|crab| crab.weight as i128 * cost.cost((pos - crab.pos as i64).abs()) as i128)
+        .sum()
+}
+
+/// Finds the position in `min..=max` of the crabs that costs the least fuel
+/// to align to, and that fuel.  If several positions tie, returns the lowest.
+fn align_with(horiz_pos: &[i32], cost: &impl FuelCost) -> (i32, i64) {
+    align_weighted(&Crab::unweighted(horiz_pos), cost)
+}
+
+/// Like [`align_with`], with each crab's fuel multiplied by its weight.
+/// Weights must not be negative.
+///
+/// When the cost is convex, so is the total cost, and the lowest position at
+/// which moving right stops saving fuel is found by binary search in
+/// O(n log range).  Otherwise every position is scanned in O(n range).
+fn align_weighted(crabs: &[Crab], cost: &impl FuelCost) -> (i32, i64) {
+    assert!(
+        crabs.iter().all(|crab| crab.weight >= 0),
+        "Weights must not be negative"
+    );
+    let min = crabs.iter().map(|crab| crab.pos).min().unwrap() as i64;
+    let max = crabs.iter().map(|crab| crab.pos).max().unwrap() as i64;
+
+    let pos = if cost.is_convex() {
+        let (mut lo, mut hi) = (min, max);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if weighted_total_cost(crabs, mid + 1, cost) >= weighted_total_cost(crabs, mid, cost) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        lo
+    } else {
+        (min..=max)
+            .min_by_key(|pos| weighted_total_cost(crabs, *pos, cost))
+            .unwrap()
+    };
+    let fuel = weighted_total_cost(crabs, pos, cost)
+        .try_into()
+        .expect("Total fuel cost overflows i64");
+    (pos as i32, fuel)
+}
+
+/// The result of aligning crabs to several target positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MultiAlignment {
+    /// The target positions, in increasing order.
+    targets: Vec<i32>,
+    /// For each crab, in input order, the index of the target it moves to.
+    assignment: Vec<usize>,
+    /// The total weighted fuel.
+    fuel: i64,
+}
+
+/// Aligns the crabs to at most `k` target positions, minimizing the total
+/// weighted fuel, e.g. k-medians for the linear cost.  Fewer targets are used
+/// if there are fewer than `k` crabs.
+///
+/// The cost must be nondecreasing in the distance, so that every crab moves to
+/// its nearest target and the crabs moving to each target are contiguous in
+/// order of position.  Dynamic programming over the crabs sorted by position
+/// then finds the best split into groups, each aligned by [`align_weighted`].
+/// This takes O(n^2) single-target alignments, so it is meant for fleets of
+/// hundreds of crabs rather than the puzzle input.
+fn align_to_targets(crabs: &[Crab], k: usize, cost: &impl FuelCost) -> MultiAlignment {
+    assert!(k > 0, "There must be at least one target");
+    assert!(!crabs.is_empty(), "There must be at least one crab");
+    let n = crabs.len();
+    let k = k.min(n);
+
+    let mut order = (0..n).collect::<Vec<_>>();
+    order.sort_by_key(|i| crabs[*i].pos);
+    let sorted = order.iter().map(|i| crabs[*i]).collect::<Vec<_>>();
+
+    // group[a][b] aligns sorted[a..b] to a single target
+    let group = (0..n)
+        .map(|a| {
+            (0..=n)
+                .map(|b| (b > a).then(|| align_weighted(&sorted[a..b], cost)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // best[j][i] is the least fuel to align sorted[..i] to j targets, and
+    // split[j][i] where the last group starts.
+    let mut best = vec![vec![i128::MAX; n + 1]; k + 1];
+    let mut split = vec![vec![0; n + 1]; k + 1];
+    best[0][0] = 0;
+    for j in 1..=k {
+        for i in j..=n {
+            for a in (j - 1)..i {
+                if best[j - 1][a] == i128::MAX {
+                    continue;
+                }
+                let fuel = best[j - 1][a] + group[a][i].unwrap().1 as i128;
+                if fuel < best[j][i] {
+                    best[j][i] = fuel;
+                    split[j][i] = a;
+                }
+            }
+        }
+    }
+
+    let mut targets = vec![0; k];
+    let mut assignment = vec![0; n];
+    let mut end = n;
+    for j in (1..=k).rev() {
+        let start = split[j][end];
+        targets[j - 1] = group[start][end].unwrap().0;
+        for i in &order[start..end] {
+            assignment[*i] = j - 1;
+        }
+        end = start;
+    }
+    MultiAlignment {
+        targets,
+        assignment,
+        fuel: best[k][n]
+            .try_into()
+            .expect("Total fuel cost overflows i64"),
+    }
+}
+
+/// The total fuel to align the crabs at each position in `min..=max` of
+/// their positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CostCurve {
+    /// The first position.
+    start: i32,
+    /// `fuel[i]` is the total fuel to align at `start + i`.
+    fuel: Vec<i64>,
+}
+
+impl CostCurve {
+    /// Computes the curve in O(n range), whatever the cost.
+    fn new(crabs: &[Crab], cost: &impl FuelCost) -> Self {
+        let min = crabs.iter().map(|crab| crab.pos).min().unwrap();
+        let max = crabs.iter().map(|crab| crab.pos).max().unwrap();
+        Self {
+            start: min,
+            fuel: (min as i64..=max as i64)
+                .map(|pos| {
+                    weighted_total_cost(crabs, pos, cost)
+                        .try_into()
+                        .expect("Total fuel cost overflows i64")
+                })
+                .collect(),
+        }
+    }
+
+    /// The total fuel to align at `pos`, or `None` outside the crabs' range.
+    fn fuel_at(&self, pos: i32) -> Option<i64> {
+        let i = pos as i64 - self.start as i64;
+        if i < 0 {
+            return None;
+        }
+        self.fuel.get(i as usize).copied()
+    }
+
+    /// Every position and the total fuel to align there, in order.
+    fn iter(&self) -> impl Iterator<Item = (i32, i64)> + '_ {
+        (self.start..).zip(self.fuel.iter().copied())
+    }
+
+    fn min_fuel(&self) -> i64 {
+        *self.fuel.iter().min().unwrap()
+    }
+
+    /// Every position that costs the least fuel, in order.
+    fn optimal_positions(&self) -> Vec<i32> {
+        let min = self.min_fuel();
+        self.iter()
+            .filter(|(_, fuel)| *fuel == min)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+}
+
+/// The fuel each crab spends to move to `pos`, in input order.
+fn fuel_by_crab(crabs: &[Crab], pos: i32, cost: &impl FuelCost) -> Vec<i64> {
+    crabs
+        .iter()
+        .map(|crab| {
+            crab.weight
+                .checked_mul(cost.cost((pos as i64 - crab.pos as i64).abs()))
+                .expect("Crab fuel cost overflows i64")
+        })
+        .collect()
+}
+
+/// Parses crab positions in `N` dimensions, e.g. `16,1 2,0 4,2` for 2-D.
+/// Each position is a comma-separated list of coordinates, and positions are
+/// separated by whitespace.  In 1-D, positions may also be separated by
+/// commas, so the puzzle input parses as is.
+fn parse_points<const N: usize>(input: &str) -> Vec<[i32; N]> {
+    if N == 1 {
+        return input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| [i32::from_str(s).unwrap(); N])
+            .collect();
+    }
+    input
+        .split_whitespace()
+        .map(|s| {
+            let coords = s
+                .split(',')
+                .map(|c| i32::from_str(c.trim()).unwrap())
+                .collect::<Vec<_>>();
+            coords
+                .try_into()
+                .unwrap_or_else(|c: Vec<i32>| panic!("Expected {} coordinates, got {}", N, c.len()))
+        })
+        .collect()
+}
+
+/// How far a crab must travel between two points.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Metric {
+    /// The sum of the distances along each axis.
+    Manhattan,
+    /// The largest distance along any axis, e.g. for crabs that may move
+    /// diagonally.
+    Chebyshev,
+    /// The straight-line distance.
+    Euclidean,
+}
+
+impl Metric {
+    fn distance<const N: usize>(&self, a: &[f64; N], b: &[f64; N]) -> f64 {
+        let deltas = a.iter().zip(b).map(|(a, b)| (a - b).abs());
+        match self {
+            Metric::Manhattan => deltas.sum(),
+            Metric::Chebyshev => deltas.fold(0.0, f64::max),
+            Metric::Euclidean => deltas.map(|d| d * d).sum::<f64>().sqrt(),
+        }
+    }
+}
+
+/// A point where the crabs can meet, and the total distance they travel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Rendezvous<const N: usize> {
+    point: [f64; N],
+    distance: f64,
+}
+
+/// The total distance for every crab to travel to `point`.
+fn total_distance<const N: usize>(points: &[[i32; N]], point: &[f64; N], metric: Metric) -> f64 {
+    points
+        .iter()
+        .map(|p| metric.distance(&p.map(|c| c as f64), point))
+        .sum()
+}
+
+/// The lower median of `values`.
+fn median<T: Ord + Copy>(mut values: Vec<T>) -> T {
+    values.sort_unstable();
+    values[(values.len() - 1) / 2]
+}
+
+/// Finds a point in `N` dimensions that minimizes the total distance the
+/// crabs travel to it.  The point need not have integer coordinates.
+///
+/// - For the Manhattan distance each axis is independent, so the
+///   coordinate-wise median is optimal.
+/// - For the Chebyshev distance in 2-D, rotating by 45 degrees to
+///   `(x + y, x - y)` turns it into half the Manhattan distance, so the median
+///   of the rotated coordinates is optimal.  In 1-D it is the Manhattan
+///   distance, and in more dimensions the problem is solved as a linear
+///   program.
+/// - For the Euclidean distance, Weiszfeld's algorithm converges to the
+///   geometric median from the centroid.
+fn rendezvous<const N: usize>(points: &[[i32; N]], metric: Metric) -> Rendezvous<N> {
+    assert!(!points.is_empty(), "There must be at least one crab");
+    let point = match (metric, N) {
+        (Metric::Manhattan, _) | (Metric::Chebyshev, 1) => {
+            let mut point = [0.0; N];
+            for (axis, coord) in point.iter_mut().enumerate() {
+                *coord = median(points.iter().map(|p| p[axis]).collect()) as f64;
+            }
+            point
+        }
+        (Metric::Chebyshev, 2) => {
+            let u = median(points.iter().map(|p| p[0] as i64 + p[1] as i64).collect()) as f64;
+            let v = median(points.iter().map(|p| p[0] as i64 - p[1] as i64).collect()) as f64;
+            let mut point = [0.0; N];
+            point[0] = (u + v) / 2.0;
+            point[1] = (u - v) / 2.0;
+            point
+        }
+        (Metric::Chebyshev, _) => chebyshev_lp(points),
+        (Metric::Euclidean, _) => weiszfeld(points),
+    };
+    Rendezvous {
+        point,
+        distance: total_distance(points, &point, metric),
+    }
+}
+
+/// The point with the least total Chebyshev distance, found by solving the
+/// linear program
+///
+/// ```text
+/// minimize  t_1 + ... + t_m
+/// such that t_j + x_a >= p_ja  and  t_j - x_a >= -p_ja  for every crab j and axis a
+/// ```
+///
+/// where `t_j` is the distance of crab `j`.  The optimum lies in the crabs'
+/// bounding box, so `x` is measured from its lowest corner to keep every
+/// variable nonnegative.  The dual program has the origin as a feasible
+/// starting point, so it is solved with the simplex method, and the primal
+/// solution read off the final tableau.
+///
+/// Every constraint has at most two nonzero coefficients, each -1 or 1, so
+/// the optimum is at a point with half-integer coordinates, which rounding
+/// recovers exactly.
+fn chebyshev_lp<const N: usize>(points: &[[i32; N]]) -> [f64; N] {
+    let mut lowest = [i32::MAX; N];
+    for p in points {
+        for axis in 0..N {
+            lowest[axis] = lowest[axis].min(p[axis]);
+        }
+    }
+
+    // The dual has a pair of variables (y+, y-) for each crab and axis, and a
+    // constraint for each t_j followed by one for each x_a:
+    //
+    //   maximize  sum of d_ja (y+_ja - y-_ja), where d_ja = p_ja - lowest_a
+    //   such that sum over a of (y+_ja + y-_ja) <= 1  for every crab j
+    //             sum over j of (y+_ja - y-_ja) <= 0  for every axis a
+    let m = points.len();
+    let vars = 2 * N * m;
+    let rows = m + N;
+    let cols = vars + rows + 1;
+    let mut tableau = vec![vec![0.0; cols]; rows + 1];
+    let mut basis = (vars..vars + rows).collect::<Vec<_>>();
+    for (j, p) in points.iter().enumerate() {
+        for axis in 0..N {
+            let plus = 2 * (j * N + axis);
+            let d = p[axis] as f64 - lowest[axis] as f64;
+            tableau[j][plus] = 1.0;
+            tableau[j][plus + 1] = 1.0;
+            tableau[m + axis][plus] = 1.0;
+            tableau[m + axis][plus + 1] = -1.0;
+            tableau[rows][plus] = -d;
+            tableau[rows][plus + 1] = d;
+        }
+        tableau[j][cols - 1] = 1.0;
+    }
+    for row in 0..rows {
+        tableau[row][vars + row] = 1.0;
+    }
+
+    // Bland's rule: enter with the lowest improving column and leave with
+    // the lowest basic variable among the tightest rows, so the many
+    // degenerate pivots cannot cycle.
+    const EPSILON: f64 = 1e-9;
+    while let Some(col) = (0..cols - 1).find(|col| tableau[rows][*col] < -EPSILON) {
+        let row = (0..rows)
+            .filter(|row| tableau[*row][col] > EPSILON)
+            .min_by(|a, b| {
+                let ratio = |row: usize| tableau[row][cols - 1] / tableau[row][col];
+                ratio(*a)
+                    .total_cmp(&ratio(*b))
+                    .then(basis[*a].cmp(&basis[*b]))
+            })
+            .expect("The dual is bounded by the crabs' distances");
+
+        let pivot = tableau[row][col];
+        tableau[row].iter_mut().for_each(|v| *v /= pivot);
+        let pivot_row = tableau[row].clone();
+        for (other, values) in tableau.iter_mut().enumerate() {
+            let factor = values[col];
+            if other != row && factor != 0.0 {
+                for (v, p) in values.iter_mut().zip(&pivot_row) {
+                    *v -= factor * p;
+                }
+            }
+        }
+        basis[row] = col;
+    }
+
+    // x_a is the reduced cost of the slack of its dual constraint
+    let mut point = [0.0; N];
+    for axis in 0..N {
+        let offset = tableau[rows][vars + m + axis];
+        point[axis] = lowest[axis] as f64 + (2.0 * offset).round() / 2.0;
+    }
+    point
+}
+
+/// The geometric median by Weiszfeld's algorithm: repeatedly move to the
+/// average of the crabs weighted by the inverse of their distance.
+///
+/// The iteration is undefined at a crab's position and converges slowly
+/// towards one, so each crab is first checked for optimality: if the pull of
+/// the other crabs, the sum of the unit vectors towards them, is no stronger
+/// than the number of crabs there, the geometric median is that crab.
+fn weiszfeld<const N: usize>(points: &[[i32; N]]) -> [f64; N] {
+    let points = points
+        .iter()
+        .map(|p| p.map(|c| c as f64))
+        .collect::<Vec<_>>();
+
+    // the pull of the other crabs on `at`, and how many crabs are there
+    let pull = |at: &[f64; N]| {
+        let mut pull = [0.0; N];
+        let mut coincident = 0;
+        for p in &points {
+            let d = Metric::Euclidean.distance(p, at);
+            if d == 0.0 {
+                coincident += 1;
+                continue;
+            }
+            for axis in 0..N {
+                pull[axis] += (p[axis] - at[axis]) / d;
+            }
+        }
+        (pull.iter().map(|c| c * c).sum::<f64>().sqrt(), coincident)
+    };
+    for p in &points {
+        let (strength, coincident) = pull(p);
+        if strength <= coincident as f64 {
+            return *p;
+        }
+    }
+
+    let mut current = [0.0; N];
+    for p in &points {
+        for axis in 0..N {
+            current[axis] += p[axis] / points.len() as f64;
+        }
+    }
+    for _ in 0..10_000 {
+        let mut weighted = [0.0; N];
+        let mut weights = 0.0;
+        for p in &points {
+            let d = Metric::Euclidean.distance(p, &current);
+            if d == 0.0 {
+                continue;
+            }
+            for axis in 0..N {
+                weighted[axis] += p[axis] / d;
+            }
+            weights += 1.0 / d;
+        }
+
+        let next = weighted.map(|c| c / weights);
+        let step = Metric::Euclidean.distance(&next, &current);
+        current = next;
+        if step < 1e-12 {
+            break;
+        }
+    }
+    current
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::utils::read_puzzle_input;
+    use crate::utils::{read_puzzle_input, SplitMix64};
 
     #[test]
     fn solve_part_1() {
@@ -160,4 +803,431 @@ mod tests {
         let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
         assert_eq!(168, align_cheapest_2(&input));
     }
+
+    #[test]
+    fn test_closed_form_puzzle() {
+        let input = parse_input(&read_puzzle_input("day7-puzzle-input.txt"));
+        assert_eq!(344735, align_median(&input));
+        assert_eq!(96798233, align_mean(&input));
+
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        assert_eq!(37, align_median(&input));
+        assert_eq!(168, align_mean(&input));
+    }
+
+    #[test]
+    fn test_closed_form_matches_brute_force() {
+        // small pseudo-random inputs, including negative and repeated positions
+        let mut rng = SplitMix64::new(12345);
+        let mut next = |bound: u64| rng.below(bound as usize) as i32;
+        for _ in 0..500 {
+            let len = 1 + next(12) as usize;
+            let spread = 1 + next(40) as u64;
+            let input = (0..len).map(|_| next(spread) - 10).collect::<Vec<_>>();
+            assert_eq!(align_cheapest(&input), align_median(&input), "{:?}", input);
+            assert_eq!(align_cheapest_2(&input), align_mean(&input), "{:?}", input);
+        }
+
+        assert_eq!(0, align_median(&[5]));
+        assert_eq!(0, align_mean(&[-5]));
+        assert_eq!(1, align_mean(&[0, 1]));
+    }
+
+    #[test]
+    fn test_fuel_costs_puzzle() {
+        let input = parse_input(&read_puzzle_input("day7-puzzle-input.txt"));
+        assert_eq!(344735, align_with(&input, &Linear).1);
+        assert_eq!(96798233, align_with(&input, &Triangular).1);
+
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        assert_eq!((2, 37), align_with(&input, &Linear));
+        assert_eq!((5, 168), align_with(&input, &Triangular));
+        // the mean is 4.9
+        assert_eq!(5, align_with(&input, &Quadratic).0);
+    }
+
+    #[test]
+    fn test_convex_search_matches_scan() {
+        let input = parse_input(&read_puzzle_input("day7-puzzle-input.txt"));
+        let scan = |cost: fn(i64) -> i64| align_with(&input, &cost);
+        assert_eq!(align_with(&input, &Linear), scan(|d| d));
+        assert_eq!(align_with(&input, &Triangular), scan(|d| d * (d + 1) / 2));
+
+        // d * (d + 1) alone would overflow an i64 here
+        assert_eq!(Triangular.cost(4_000_000_000), 8_000_000_002_000_000_000);
+        assert_eq!(align_with(&input, &Quadratic), scan(|d| d * d));
+        assert_eq!(
+            align_with(&input, &Convex(|d: i64| d * d * d)),
+            scan(|d| d * d * d)
+        );
+
+        // ties resolve to the lowest position either way
+        let input = vec![0, 10];
+        assert_eq!((0, 10), align_with(&input, &Linear));
+        assert_eq!((0, 10), align_with(&input, &|d: i64| d));
+    }
+
+    #[test]
+    fn test_non_convex_costs() {
+        // moving more than three steps costs the same, so it is cheapest to
+        // gather the crabs that are close together and abandon the rest.
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        let capped = Capped {
+            cost: Triangular,
+            cap: 6,
+        };
+        assert_eq!((2, 26), align_with(&input, &capped));
+
+        // a cost that rewards travelling exactly five steps
+        let cost = |d: i64| if d == 5 { 0 } else { d };
+        let input = vec![0, 10];
+        assert_eq!((5, 0), align_with(&input, &cost));
+    }
+
+    #[test]
+    fn test_weighted_alignment() {
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        let crabs = Crab::unweighted(&input);
+        assert_eq!((2, 37), align_weighted(&crabs, &Linear));
+
+        // a heavy enough crab pulls the alignment to itself
+        let mut crabs = crabs;
+        crabs[0].weight = 10;
+        assert_eq!(
+            (16, 15 + 14 + 16 + 12 + 14 + 9 + 15 + 14 + 2),
+            align_weighted(&crabs, &Linear)
+        );
+
+        // weights agree with repeating a crab
+        let crabs = vec![Crab { pos: 0, weight: 3 }, Crab { pos: 10, weight: 2 }];
+        let repeated = vec![0, 0, 0, 10, 10];
+        assert_eq!(
+            align_with(&repeated, &Triangular),
+            align_weighted(&crabs, &Triangular)
+        );
+        assert_eq!(
+            align_with(&repeated, &|d: i64| d),
+            align_weighted(&crabs, &|d: i64| d)
+        );
+    }
+
+    #[test]
+    fn test_multi_target_alignment() {
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        let crabs = Crab::unweighted(&input);
+
+        let one = align_to_targets(&crabs, 1, &Linear);
+        assert_eq!(one.targets, vec![2]);
+        assert_eq!(one.fuel, 37);
+        assert_eq!(one.assignment, vec![0; 10]);
+
+        // the far crabs at 14 and 16 get their own target
+        let two = align_to_targets(&crabs, 2, &Linear);
+        assert_eq!(two.targets, vec![2, 14]);
+        assert_eq!(two.assignment, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(two.fuel, 2 + 1 + 2 + 2 + 5 + 1);
+
+        // as many targets as distinct positions cost nothing
+        let many = align_to_targets(&crabs, 7, &Linear);
+        assert_eq!(many.fuel, 0);
+        assert_eq!(many.targets, vec![0, 1, 2, 4, 7, 14, 16]);
+        let capped = align_to_targets(&crabs[..3], 5, &Linear);
+        assert_eq!(capped.targets.len(), 3);
+        assert_eq!(capped.fuel, 0);
+    }
+
+    #[test]
+    fn test_multi_target_matches_brute_force() {
+        // try every pair of targets within the range
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14, 9, 9, 3];
+        let crabs = input
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| Crab {
+                pos: *pos,
+                weight: 1 + (i as i64 * 7) % 4,
+            })
+            .collect::<Vec<_>>();
+        for cost in [
+            &(|d: i64| d * (d + 1) / 2) as &dyn Fn(i64) -> i64,
+            &|d: i64| d * d,
+            &|d: i64| d.min(5),
+        ] {
+            let mut best = i64::MAX;
+            for t1 in 0..=16_i64 {
+                for t2 in t1..=16 {
+                    let fuel = crabs
+                        .iter()
+                        .map(|c| {
+                            let d1 = (c.pos as i64 - t1).abs();
+                            let d2 = (c.pos as i64 - t2).abs();
+                            c.weight * cost(d1).min(cost(d2))
+                        })
+                        .sum::<i64>();
+                    best = best.min(fuel);
+                }
+            }
+            let aligned = align_to_targets(&crabs, 2, &cost);
+            assert_eq!(aligned.fuel, best);
+            let fuel = crabs
+                .iter()
+                .zip(&aligned.assignment)
+                .map(|(c, t)| c.weight * cost((c.pos - aligned.targets[*t]).abs() as i64))
+                .sum::<i64>();
+            assert_eq!(fuel, aligned.fuel);
+        }
+    }
+
+    #[test]
+    fn test_cost_curve() {
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        let crabs = Crab::unweighted(&input);
+
+        let curve = CostCurve::new(&crabs, &Linear);
+        assert_eq!(curve.iter().count(), 17);
+        assert_eq!(curve.fuel_at(1), Some(41));
+        assert_eq!(curve.fuel_at(2), Some(37));
+        assert_eq!(curve.fuel_at(3), Some(39));
+        assert_eq!(curve.fuel_at(10), Some(71));
+        assert_eq!(curve.fuel_at(-1), None);
+        assert_eq!(curve.fuel_at(17), None);
+        assert_eq!(curve.optimal_positions(), vec![2]);
+        assert_eq!(
+            fuel_by_crab(&crabs, 2, &Linear),
+            vec![14, 1, 0, 2, 2, 0, 5, 1, 0, 12]
+        );
+
+        let curve = CostCurve::new(&crabs, &Triangular);
+        assert_eq!(curve.fuel_at(2), Some(206));
+        assert_eq!(curve.min_fuel(), 168);
+        assert_eq!(curve.optimal_positions(), vec![5]);
+        assert_eq!(
+            fuel_by_crab(&crabs, 5, &Triangular),
+            vec![66, 10, 6, 15, 1, 6, 3, 10, 6, 45]
+        );
+    }
+
+    #[test]
+    fn test_cost_curve_ties() {
+        // any position between two crabs costs the same
+        let crabs = Crab::unweighted(&[0, 10]);
+        let curve = CostCurve::new(&crabs, &Linear);
+        assert_eq!(curve.optimal_positions(), (0..=10).collect::<Vec<_>>());
+        assert_eq!(align_with(&[0, 10], &Linear), (0, 10));
+
+        let crabs = Crab::unweighted(&[0, 1]);
+        let curve = CostCurve::new(&crabs, &Triangular);
+        assert_eq!(curve.optimal_positions(), vec![0, 1]);
+        assert_eq!(curve.fuel, vec![1, 1]);
+
+        let input = parse_input(&read_puzzle_input("day7-puzzle-input.txt"));
+        let curve = CostCurve::new(&Crab::unweighted(&input), &Triangular);
+        assert_eq!(curve.min_fuel(), 96798233);
+        let pos = curve.optimal_positions()[0];
+        assert_eq!(
+            fuel_by_crab(&Crab::unweighted(&input), pos, &Triangular)
+                .iter()
+                .sum::<i64>(),
+            96798233
+        );
+    }
+
+    #[test]
+    fn test_wide_range_does_not_overflow() {
+        // 200_000 steps cost about 2 * 10^10 fuel, more than an i32 holds
+        let input = vec![0, 200_000, 0];
+        assert_eq!(align_cheapest(&input), 200_000);
+        assert_eq!(align_median(&input), 200_000);
+        assert_eq!(align_cheapest_2(&input), 13333466667);
+        assert_eq!(align_mean(&input), 13333466667);
+        assert_eq!(align_with(&input, &Triangular), (66666, 13333466667));
+
+        // halfway to the far crab the quadratic total is about 2.4 * 10^18
+        // per crab, so the search only works if it is summed in i128
+        let mut input = vec![0; 8];
+        input.push(i32::MAX);
+        let pos = i32::MAX / 9;
+        let fuel = 8 * (pos as i64).pow(2) + (i32::MAX - pos) as i64 * (i32::MAX - pos) as i64;
+        assert_eq!(align_with(&input, &Quadratic), (pos, fuel));
+
+        // across the whole i32 range, weighted fuel overflows an i64 everywhere
+        // but at the heavier end
+        let crabs = vec![
+            Crab {
+                pos: i32::MIN,
+                weight: 1 << 31,
+            },
+            Crab {
+                pos: i32::MAX,
+                weight: (1 << 31) + 1,
+            },
+        ];
+        assert_eq!(
+            align_weighted(&crabs, &Linear),
+            (i32::MAX, (1 << 31) * u32::MAX as i64)
+        );
+        assert_eq!(Quadratic.cost(3_000_000_000), 9_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_points() {
+        assert_eq!(
+            parse_points::<2>("16,1 2,0\n4,2\n"),
+            vec![[16, 1], [2, 0], [4, 2]]
+        );
+        assert_eq!(parse_points::<3>("1,2,3"), vec![[1, 2, 3]]);
+        assert_eq!(parse_points::<1>("16 1 2"), vec![[16], [1], [2]]);
+        assert_eq!(parse_points::<1>("16,1,2\n"), vec![[16], [1], [2]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 2 coordinates, got 3")]
+    fn test_parse_points_wrong_dimension() {
+        parse_points::<2>("1,2 1,2,3");
+    }
+
+    #[test]
+    fn test_one_dimensional_rendezvous() {
+        let input = read_puzzle_input("day7-puzzle-input.txt");
+        let points = parse_points::<1>(&input);
+        assert_eq!(
+            points.iter().map(|p| p[0]).collect::<Vec<_>>(),
+            parse_input(&input)
+        );
+        for metric in [Metric::Manhattan, Metric::Chebyshev] {
+            assert_eq!(rendezvous(&points, metric).distance, 344735.0);
+        }
+        let euclidean = rendezvous(&points, Metric::Euclidean).distance;
+        assert!((euclidean - 344735.0).abs() < 1e-6);
+    }
+
+    /// The least total distance over the points of a grid with the given
+    /// spacing covering the crabs.
+    fn grid_minimum(points: &[[i32; 2]], metric: Metric, spacing: f64) -> f64 {
+        let steps = (20.0 / spacing) as i32;
+        let mut best = f64::INFINITY;
+        for i in 0..=steps {
+            for j in 0..=steps {
+                let point = [i as f64 * spacing, j as f64 * spacing];
+                best = best.min(total_distance(points, &point, metric));
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_two_dimensional_rendezvous() {
+        let points = parse_points::<2>("16,1 1,2 2,0 0,4 4,2 2,7 7,1 1,2 2,14 14,16 3,3");
+
+        let manhattan = rendezvous(&points, Metric::Manhattan);
+        assert_eq!(manhattan.point, [2.0, 2.0]);
+        assert_eq!(
+            manhattan.distance,
+            grid_minimum(&points, Metric::Manhattan, 1.0)
+        );
+
+        // the Chebyshev optimum may lie on half-integer coordinates
+        let chebyshev = rendezvous(&points, Metric::Chebyshev);
+        assert_eq!(
+            chebyshev.distance,
+            grid_minimum(&points, Metric::Chebyshev, 0.5)
+        );
+        let chebyshev = rendezvous(&points[..4], Metric::Chebyshev);
+        assert_eq!(
+            chebyshev.distance,
+            grid_minimum(&points[..4], Metric::Chebyshev, 0.5)
+        );
+
+        let euclidean = rendezvous(&points, Metric::Euclidean);
+        assert!(euclidean.distance <= grid_minimum(&points, Metric::Euclidean, 0.05) + 1e-9);
+
+        // the rotated coordinates do not fit in an i32
+        let far = [
+            [i32::MAX, i32::MAX],
+            [i32::MAX - 2, i32::MAX],
+            [i32::MIN, i32::MIN],
+        ];
+        let chebyshev = rendezvous(&far[..2], Metric::Chebyshev);
+        assert_eq!(chebyshev.distance, 2.0);
+        let chebyshev = rendezvous(&far, Metric::Chebyshev);
+        assert_eq!(
+            chebyshev.point,
+            [i32::MAX as f64 - 1.0, i32::MAX as f64 - 1.0]
+        );
+        assert_eq!(chebyshev.distance, 2_f64.powi(32));
+    }
+
+    #[test]
+    fn test_euclidean_rendezvous() {
+        let square = parse_points::<2>("0,0 2,0 0,2 2,2");
+        let meet = rendezvous(&square, Metric::Euclidean);
+        assert!((meet.point[0] - 1.0).abs() < 1e-9 && (meet.point[1] - 1.0).abs() < 1e-9);
+        assert!((meet.distance - 4.0 * 2_f64.sqrt()).abs() < 1e-9);
+
+        // on a line, the geometric median is the median, which is a crab
+        let line = parse_points::<2>("0,0 1,0 5,0");
+        let meet = rendezvous(&line, Metric::Euclidean);
+        assert_eq!(meet.point, [1.0, 0.0]);
+        assert_eq!(meet.distance, 5.0);
+
+        // three crabs in a corner outweigh the pull of the other two
+        let corner = parse_points::<2>("0,0 0,0 0,0 10,0 0,10");
+        assert_eq!(rendezvous(&corner, Metric::Euclidean).point, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_three_dimensional_rendezvous() {
+        let points = parse_points::<3>("0,0,0 4,1,0 1,5,2 3,3,3 0,2,6");
+        let manhattan = rendezvous(&points, Metric::Manhattan);
+        assert_eq!(manhattan.point, [1.0, 2.0, 2.0]);
+
+        // compare with every half-integer point nearby
+        let chebyshev = rendezvous(&points, Metric::Chebyshev);
+        let mut best = f64::INFINITY;
+        for x in -4..16 {
+            for y in -4..16 {
+                for z in -4..16 {
+                    let point = [x as f64 / 2.0, y as f64 / 2.0, z as f64 / 2.0];
+                    best = best.min(total_distance(&points, &point, Metric::Chebyshev));
+                }
+            }
+        }
+        assert_eq!(chebyshev.distance, best);
+
+        // no integer point is optimal here
+        let points = parse_points::<3>("1,2,2 4,3,4 3,4,0");
+        let chebyshev = rendezvous(&points, Metric::Chebyshev);
+        assert_eq!(chebyshev.point, [1.5, 2.5, 1.5]);
+        assert_eq!(chebyshev.distance, 4.5);
+
+        // the puzzle's positions, taken three at a time
+        let input = parse_input(&read_puzzle_input("day7-puzzle-input.txt"));
+        let points = input
+            .chunks_exact(3)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect::<Vec<_>>();
+        let chebyshev = rendezvous(&points, Metric::Chebyshev);
+        let center = points.iter().fold([0.0; 3], |mut sum, p| {
+            for axis in 0..3 {
+                sum[axis] += p[axis] as f64 / points.len() as f64;
+            }
+            sum
+        });
+        assert!(chebyshev.distance <= total_distance(&points, &center, Metric::Chebyshev));
+        for delta in [
+            [0.5, 0.0, 0.0],
+            [0.0, -0.5, 0.0],
+            [0.0, 0.0, 0.5],
+            [0.5, 0.5, -0.5],
+        ] {
+            let mut nearby = chebyshev.point;
+            for axis in 0..3 {
+                nearby[axis] += delta[axis];
+            }
+            assert!(chebyshev.distance <= total_distance(&points, &nearby, Metric::Chebyshev));
+        }
+
+        let euclidean = rendezvous(&points, Metric::Euclidean);
+        assert!(euclidean.distance <= total_distance(&points, &manhattan.point, Metric::Euclidean));
+    }
 }
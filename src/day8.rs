@@ -168,8 +168,8 @@
 //! four-digit output values. What do you get if you add up all of the output
 //! values?
 
-use std::collections::{BTreeMap, HashSet};
-use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Display, Formatter};
 
 fn parse_input(input: &str) -> Vec<(Vec<&str>, Vec<&str>)> {
     input
@@ -185,9 +185,113 @@ fn parse_input(input: &str) -> Vec<(Vec<&str>, Vec<&str>)> {
         .collect()
 }
 
-fn build_decoder_by_intersecting(patterns: &[&str]) -> SevenSegmentDisplayEncoding {
-    let c = Vec::<HashSet<&str>>::new();
-    todo!()
+/// The ways in which the ten patterns of an entry can fail to describe a
+/// scrambled seven-segment display.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DecodeError {
+    /// There are not exactly ten patterns.
+    PatternCount(usize),
+    /// The same pattern appears more than once.
+    DuplicatePattern,
+    /// A pattern uses a wire other than `a` through `g`.
+    InvalidWire(char),
+    /// There is not exactly one pattern with this many segments, as there is
+    /// for 1, 4 and 7.
+    AmbiguousLength(usize),
+    /// The patterns do not match the digits of any wiring.
+    Inconsistent,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::PatternCount(n) => write!(f, "expected 10 patterns, got {}", n),
+            DecodeError::DuplicatePattern => f.write_str("patterns are not distinct"),
+            DecodeError::InvalidWire(c) => write!(f, "invalid wire {:?}", c),
+            DecodeError::AmbiguousLength(len) => {
+                write!(f, "expected exactly one pattern with {} segments", len)
+            }
+            DecodeError::Inconsistent => f.write_str("patterns do not match any wiring"),
+        }
+    }
+}
+
+/// Deduces the wiring from set operations over the patterns.
+///
+/// Across the ten digits, segments b, e and f are lit 6, 4 and 9 times, which
+/// no other segment is.  Of the rest, a and c are lit 8 times and d and g 7
+/// times.  Then a is in 7 but not 1, c is in 1 but is not f, and d is in 4
+/// while g is not.
+fn build_decoder_by_intersecting(
+    patterns: &[&str],
+) -> Result<SevenSegmentDisplayEncoding, DecodeError> {
+    let patterns = patterns
+        .iter()
+        .map(|p| p.chars().collect::<BTreeSet<_>>())
+        .collect::<Vec<_>>();
+    if patterns.len() != 10 {
+        return Err(DecodeError::PatternCount(patterns.len()));
+    }
+    if patterns.iter().collect::<HashSet<_>>().len() != patterns.len() {
+        return Err(DecodeError::DuplicatePattern);
+    }
+    if let Some(c) = patterns
+        .iter()
+        .flatten()
+        .find(|c| !('a'..='g').contains(*c))
+    {
+        return Err(DecodeError::InvalidWire(*c));
+    }
+
+    let with_length = |len: usize| {
+        let mut matching = patterns.iter().filter(|p| p.len() == len);
+        match (matching.next(), matching.next()) {
+            (Some(p), None) => Ok(p),
+            _ => Err(DecodeError::AmbiguousLength(len)),
+        }
+    };
+    let one = with_length(2)?;
+    let four = with_length(4)?;
+    let seven = with_length(3)?;
+
+    let with_frequency = |n: usize| {
+        ('a'..='g')
+            .filter(|c| patterns.iter().filter(|p| p.contains(c)).count() == n)
+            .collect::<BTreeSet<_>>()
+    };
+    let single = |set: BTreeSet<char>| {
+        let mut iter = set.into_iter();
+        match (iter.next(), iter.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(DecodeError::Inconsistent),
+        }
+    };
+
+    let b = single(with_frequency(6))?;
+    let e = single(with_frequency(4))?;
+    let f = single(with_frequency(9))?;
+    let a = single(seven - one)?;
+    let c = single(one - &BTreeSet::from([f]))?;
+    let d = single(four & &with_frequency(7))?;
+    let g = single(&with_frequency(7) - four)?;
+    if with_frequency(8) != BTreeSet::from([a, c]) {
+        return Err(DecodeError::Inconsistent);
+    }
+    if [a, b, c, d, e, f, g].iter().collect::<HashSet<_>>().len() != 7 {
+        return Err(DecodeError::Inconsistent);
+    }
+
+    // every pattern must now be a different digit
+    let decoder = SevenSegmentDisplayEncoding::new(a, b, c, d, e, f, g);
+    let digits = patterns
+        .iter()
+        .map(|p| decoder.try_decode(&p.iter().collect::<String>()))
+        .collect::<Option<HashSet<_>>>()
+        .ok_or(DecodeError::Inconsistent)?;
+    if digits.len() != 10 {
+        return Err(DecodeError::Inconsistent);
+    }
+    Ok(decoder)
 }
 
 /// Each pattern can be mapped to possible digits by considering the number of
@@ -290,9 +394,12 @@ fn build_decoder(patterns: &[&str]) -> SevenSegmentDisplayEncoding {
     SevenSegmentDisplayEncoding::new(a, b, c, d, e, f, g)
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 struct SevenSegmentDisplayEncoding {
     map: BTreeMap<char, char>,
+    /// The digit shown by each scrambled pattern, indexed by its
+    /// [`pattern_mask`].
+    digits: [Option<u8>; 128],
 }
 
 impl SevenSegmentDisplayEncoding {
@@ -309,56 +416,658 @@ impl SevenSegmentDisplayEncoding {
         assert!(map.insert(e, 'e').is_none());
         assert!(map.insert(f, 'f').is_none());
         assert!(map.insert(g, 'g').is_none());
-        Self { map }
+
+        assert!(map.keys().all(|w| ('a'..='g').contains(w)));
+
+        // scramble the segments of each digit
+        let wires = [a, b, c, d, e, f, g].map(|w| 1 << (w as u8 - b'a'));
+        let mut digits = [None; 128];
+        for (digit, segments) in SEVEN_SEGMENT_MASKS.iter().enumerate() {
+            let scrambled = (0..7)
+                .filter(|s| segments & 1 << s != 0)
+                .fold(0, |mask, s| mask | wires[s]);
+            digits[scrambled] = Some(digit as u8);
+        }
+        Self { map, digits }
     }
 
     fn decode(&self, input: &str) -> i32 {
-        let mut on = [0; 7];
-        input
+        self.try_decode(input)
+            .unwrap_or_else(|| panic!("Unable to decode {}", input))
+    }
+
+    /// The digit shown by the pattern, or `None` if it is not a digit.
+    fn try_decode(&self, input: &str) -> Option<i32> {
+        self.decode_mask(pattern_mask(input)?).map(|d| d as i32)
+    }
+
+    /// The digit shown by the scrambled pattern with the given
+    /// [`pattern_mask`], or `None` if it is not a digit.
+    fn decode_mask(&self, mask: u8) -> Option<u8> {
+        *self.digits.get(mask as usize)?
+    }
+
+    fn decode_slice(&self, input: &[&str]) -> i32 {
+        input.iter().fold(0, |value, s| value * 10 + self.decode(s))
+    }
+
+    /// The number shown by the scrambled patterns, most significant digit
+    /// first, or `None` if a pattern is not a digit.
+    fn decode_masks(&self, masks: &[u8]) -> Option<u32> {
+        masks.iter().try_fold(0, |value, mask| {
+            Some(value * 10 + self.decode_mask(*mask)? as u32)
+        })
+    }
+}
+
+/// Which names to draw the lit segments of a rendered display with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SegmentLabels {
+    /// The segment's own name.
+    Real,
+    /// The name of the wire connected to the segment.
+    Scrambled,
+}
+
+impl SevenSegmentDisplayEncoding {
+    /// The wire connected to a segment, or `None` if it is not a segment.
+    fn wire_for(&self, segment: char) -> Option<char> {
+        self.map
+            .iter()
+            .find(|(_, real)| **real == segment)
+            .map(|(wire, _)| *wire)
+    }
+
+    /// The scrambled pattern that shows a digit, with its wires in
+    /// alphabetical order, or `None` if `digit` is not a single digit.
+    fn encode_digit(&self, digit: u8) -> Option<String> {
+        let (_, segments) = SEVEN_SEGMENT_DIGITS.get(digit as usize)?;
+        let mut wires = segments
             .chars()
-            .for_each(|c| on[self.map[&c] as usize - 'a' as usize] = 1);
-        match on {
-            //a,b, c, d, e, f, g,
-            [1, 1, 1, 0, 1, 1, 1] => 0,
-            [0, 0, 1, 0, 0, 1, 0] => 1,
-            [1, 0, 1, 1, 1, 0, 1] => 2,
-            [1, 0, 1, 1, 0, 1, 1] => 3,
-            [0, 1, 1, 1, 0, 1, 0] => 4,
-            [1, 1, 0, 1, 0, 1, 1] => 5,
-            [1, 1, 0, 1, 1, 1, 1] => 6,
-            [1, 0, 1, 0, 0, 1, 0] => 7,
-            [1, 1, 1, 1, 1, 1, 1] => 8,
-            [1, 1, 1, 1, 0, 1, 1] => 9,
-            _ => panic!("Unable to decode {}", input),
+            .map(|s| self.wire_for(s))
+            .collect::<Option<Vec<_>>>()?;
+        wires.sort_unstable();
+        Some(wires.into_iter().collect())
+    }
+
+    /// The scrambled patterns that show a number on a display of `width`
+    /// digits, padded with leading zeros, or `None` if the number does not
+    /// fit.
+    fn encode(&self, number: u32, width: usize) -> Option<Vec<String>> {
+        let digits = format!("{:0width$}", number, width = width);
+        if digits.len() != width {
+            return None;
         }
+        digits
+            .bytes()
+            .map(|d| self.encode_digit(d - b'0'))
+            .collect()
     }
 
-    fn decode_slice(&self, input: &[&str]) -> i32 {
-        let mut val_as_string = String::with_capacity(input.len());
-
-        for s in input {
-            let mut on = [0; 7];
-            s.chars()
-                .for_each(|c| on[self.map[&c] as usize - 'a' as usize] = 1);
-            let digit = match on {
-                //a,b, c, d, e, f, g,
-                [1, 1, 1, 0, 1, 1, 1] => 0,
-                [0, 0, 1, 0, 0, 1, 0] => 1,
-                [1, 0, 1, 1, 1, 0, 1] => 2,
-                [1, 0, 1, 1, 0, 1, 1] => 3,
-                [0, 1, 1, 1, 0, 1, 0] => 4,
-                [1, 1, 0, 1, 0, 1, 1] => 5,
-                [1, 1, 0, 1, 1, 1, 1] => 6,
-                [1, 0, 1, 0, 0, 1, 0] => 7,
-                [1, 1, 1, 1, 1, 1, 1] => 8,
-                [1, 1, 1, 1, 0, 1, 1] => 9,
-                _ => panic!("Unable to decode {}", s),
-            };
-
-            val_as_string.push(char::from_digit(digit, 10).unwrap());
+    /// Draws the scrambled patterns side by side in the style of the puzzle,
+    /// with unlit segments drawn as `.`, or returns `None` if a pattern uses a
+    /// wire the display does not have.
+    fn render(&self, patterns: &[&str], labels: SegmentLabels) -> Option<String> {
+        // the character to draw for each real segment of each pattern
+        let glyphs = patterns
+            .iter()
+            .map(|p| {
+                let mut lit = ['.'; 7];
+                for wire in p.chars() {
+                    let segment = *self.map.get(&wire)?;
+                    lit[segment as usize - 'a' as usize] = match labels {
+                        SegmentLabels::Real => segment,
+                        SegmentLabels::Scrambled => wire,
+                    };
+                }
+                Some(lit)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        // each row of a glyph, from the lit segments
+        let row = |g: &[char; 7], row: usize| {
+            let horizontal = |c: char| format!(" {} ", c.to_string().repeat(4));
+            let vertical = |left: char, right: char| format!("{}    {}", left, right);
+            match row {
+                0 => horizontal(g[0]),
+                1 | 2 => vertical(g[1], g[2]),
+                3 => horizontal(g[3]),
+                4 | 5 => vertical(g[4], g[5]),
+                _ => horizontal(g[6]),
+            }
+        };
+
+        let mut out = String::new();
+        for r in 0..7 {
+            let line = glyphs
+                .iter()
+                .map(|g| row(g, r))
+                .collect::<Vec<_>>()
+                .join("  ");
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        Some(out)
+    }
+}
+
+/// A pattern of the wires `a` through `g` as a bitmask, with `a` as the lowest
+/// bit, or `None` if it uses any other wire.
+fn pattern_mask(pattern: &str) -> Option<u8> {
+    pattern.bytes().try_fold(0, |mask, c| match c {
+        b'a'..=b'g' => Some(mask | 1 << (c - b'a')),
+        _ => None,
+    })
+}
+
+/// The segments lit for each digit of a standard seven-segment display.
+const SEVEN_SEGMENT_DIGITS: &[(char, &str)] = &[
+    ('0', "abcefg"),
+    ('1', "cf"),
+    ('2', "acdeg"),
+    ('3', "acdfg"),
+    ('4', "bcdf"),
+    ('5', "abdfg"),
+    ('6', "abdefg"),
+    ('7', "acf"),
+    ('8', "abcdefg"),
+    ('9', "abcdfg"),
+];
+
+/// The segments of each digit of [`SEVEN_SEGMENT_DIGITS`] as a bitmask, with
+/// segment `a` as the lowest bit, indexed by digit.
+const SEVEN_SEGMENT_MASKS: [u32; 10] = {
+    let mut masks = [0; 10];
+    let mut digit = 0;
+    while digit < 10 {
+        let segments = SEVEN_SEGMENT_DIGITS[digit].1.as_bytes();
+        let mut i = 0;
+        while i < segments.len() {
+            masks[digit] |= 1 << (segments[i] - b'a');
+            i += 1;
+        }
+        digit += 1;
+    }
+    masks
+};
+
+/// Forms of 6, 7 and 9 that some seven-segment displays use instead: 6
+/// without the top segment, 7 with the upper left segment and 9 without the
+/// bottom segment.
+const SEVEN_SEGMENT_ALTERNATES: &[(char, &str)] = &[('6', "bdefg"), ('7', "abcf"), ('9', "abcdf")];
+
+/// The digits and letters of a fourteen-segment display, whose segments are
+/// named as follows:
+///
+/// ```text
+///  aaaaaaa
+/// fi  j  kb
+/// f i j k b
+///  gggghhh
+/// e l m n c
+/// el  m  nc
+///  ddddddd
+/// ```
+const FOURTEEN_SEGMENT_GLYPHS: &[(char, &str)] = &[
+    ('0', "abcdefkl"),
+    ('1', "bc"),
+    ('2', "abdegh"),
+    ('3', "abcdh"),
+    ('4', "bcfgh"),
+    ('5', "acdfgh"),
+    ('6', "acdefgh"),
+    ('7', "abc"),
+    ('8', "abcdefgh"),
+    ('9', "abcdfgh"),
+    ('A', "abcefgh"),
+    ('B', "abcdhjm"),
+    ('C', "adef"),
+    ('D', "abcdjm"),
+    ('E', "adefg"),
+    ('F', "aefg"),
+    ('G', "acdefh"),
+    ('H', "bcefgh"),
+    ('I', "adjm"),
+    ('J', "bcde"),
+    ('K', "efgkn"),
+    ('L', "def"),
+    ('M', "bcefik"),
+    ('N', "bcefin"),
+    ('O', "abcdef"),
+    ('P', "abefgh"),
+    ('Q', "abcdefn"),
+    ('R', "abefghn"),
+    ('S', "acdhi"),
+    ('T', "ajm"),
+    ('U', "bcdef"),
+    ('V', "efkl"),
+    ('W', "bcefln"),
+    ('X', "ikln"),
+    ('Y', "ikm"),
+    ('Z', "adkl"),
+];
+
+/// The bitmask of the segments named in `pattern`, with segment `a` as the
+/// lowest bit, or `None` if a name is not a lowercase letter.
+fn segment_mask(pattern: &str) -> Option<u32> {
+    pattern.chars().try_fold(0, |mask, c| {
+        c.is_ascii_lowercase()
+            .then(|| mask | 1 << (c as u32 - 'a' as u32))
+    })
+}
+
+/// A kind of segment display: its segments, named `a`, `b`, ... in order, and
+/// the segments each glyph lights.  A glyph may have several forms.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct DisplayDefinition {
+    segments: usize,
+    /// Each glyph and a bitmask of the segments it lights.
+    glyphs: Vec<(char, u32)>,
+}
+
+impl DisplayDefinition {
+    fn new(segments: usize, glyphs: &[(char, &str)]) -> Self {
+        assert!(segments <= 26, "Segments are named by single letters");
+        let glyphs = glyphs
+            .iter()
+            .map(|(glyph, pattern)| {
+                let mask = segment_mask(pattern)
+                    .filter(|mask| *mask >> segments == 0)
+                    .unwrap_or_else(|| panic!("Invalid segments {:?} for {}", pattern, glyph));
+                (*glyph, mask)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            glyphs
+                .iter()
+                .map(|(_, mask)| mask)
+                .collect::<HashSet<_>>()
+                .len(),
+            glyphs.len(),
+            "Every form of every glyph must light different segments"
+        );
+        Self { segments, glyphs }
+    }
+
+    fn seven_segment() -> Self {
+        Self::new(7, SEVEN_SEGMENT_DIGITS)
+    }
+
+    /// Seven segments, where 6, 7 and 9 may also take their alternate forms.
+    fn seven_segment_with_alternates() -> Self {
+        Self::new(
+            7,
+            &[SEVEN_SEGMENT_DIGITS, SEVEN_SEGMENT_ALTERNATES].concat(),
+        )
+    }
+
+    fn fourteen_segment() -> Self {
+        Self::new(14, FOURTEEN_SEGMENT_GLYPHS)
+    }
+
+    /// The glyph that lights exactly the segments in `mask`.
+    fn glyph(&self, mask: u32) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|(_, m)| *m == mask)
+            .map(|(glyph, _)| *glyph)
+    }
+}
+
+/// The ways in which observed patterns can fail to determine the wiring of a
+/// display.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SolveError {
+    /// A pattern uses a wire that the display does not have.
+    InvalidWire(char),
+    /// No wiring makes every pattern a glyph.
+    NoSolution,
+    /// More than one wiring makes every pattern a glyph.
+    Ambiguous,
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::InvalidWire(c) => write!(f, "invalid wire {:?}", c),
+            SolveError::NoSolution => f.write_str("no wiring matches the patterns"),
+            SolveError::Ambiguous => f.write_str("more than one wiring matches the patterns"),
+        }
+    }
+}
+
+/// Decodes the patterns of a display whose wires have been solved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct DisplayDecoder<'a> {
+    definition: &'a DisplayDefinition,
+    /// The segment that each wire, `a`, `b`, ..., is connected to.
+    wire_to_segment: Vec<usize>,
+}
+
+impl<'a> DisplayDecoder<'a> {
+    /// The segments lit by the pattern of wires, or `None` if it uses a wire
+    /// that the display does not have.
+    fn segments(&self, pattern: &str) -> Option<u32> {
+        let mut mask = 0;
+        for c in pattern.chars() {
+            let wire = (c as u32).checked_sub('a' as u32)? as usize;
+            mask |= 1 << self.wire_to_segment.get(wire)?;
+        }
+        Some(mask)
+    }
+
+    /// The glyph shown by the pattern of wires, or `None` if it is not a
+    /// glyph.
+    fn decode(&self, pattern: &str) -> Option<char> {
+        self.definition.glyph(self.segments(pattern)?)
+    }
+
+    fn decode_all(&self, patterns: &[&str]) -> Option<String> {
+        patterns.iter().map(|p| self.decode(p)).collect()
+    }
+
+    /// The glyph closest to the pattern of wires, counting the segments that
+    /// would have to be flipped, or `None` if different glyphs are equally
+    /// close.
+    fn decode_nearest(&self, pattern: &str) -> Option<char> {
+        let mask = self.segments(pattern)?;
+        let distance = |glyph_mask: u32| (mask ^ glyph_mask).count_ones();
+        let nearest = self
+            .definition
+            .glyphs
+            .iter()
+            .map(|(_, glyph_mask)| distance(*glyph_mask))
+            .min()?;
+        let mut glyphs = self
+            .definition
+            .glyphs
+            .iter()
+            .filter(|(_, glyph_mask)| distance(*glyph_mask) == nearest)
+            .map(|(glyph, _)| *glyph);
+        let glyph = glyphs.next()?;
+        glyphs.all(|g| g == glyph).then_some(glyph)
+    }
+}
+
+/// The wires used by each pattern, as bitmasks with wire `a` as the lowest
+/// bit.
+fn wire_masks(definition: &DisplayDefinition, patterns: &[&str]) -> Result<Vec<u32>, SolveError> {
+    patterns
+        .iter()
+        .map(|p| {
+            let mask = segment_mask(p).ok_or(SolveError::InvalidWire(
+                p.chars().find(|c| !c.is_ascii_lowercase()).unwrap_or(' '),
+            ))?;
+            if mask >> definition.segments != 0 {
+                let wire = (definition.segments..32)
+                    .find(|i| mask & 1 << i != 0)
+                    .unwrap();
+                return Err(SolveError::InvalidWire((b'a' + wire as u8) as char));
+            }
+            Ok(mask)
+        })
+        .collect()
+}
+
+/// Finds the wiring under which every observed pattern is a glyph of the
+/// display.  See [`WiringSolver`] for how.
+fn solve_wiring<'a>(
+    definition: &'a DisplayDefinition,
+    patterns: &[&str],
+) -> Result<DisplayDecoder<'a>, SolveError> {
+    let patterns = wire_masks(definition, patterns)?;
+    let mut solutions = WiringSolver::new(definition, &patterns, 0, 2).solve();
+    match solutions.len() {
+        0 => Err(SolveError::NoSolution),
+        1 => Ok(DisplayDecoder {
+            definition,
+            wire_to_segment: solutions.pop().unwrap().wire_to_segment,
+        }),
+        _ => Err(SolveError::Ambiguous),
+    }
+}
+
+/// A wiring of a display, and how many segments must have been flipped for
+/// the observed patterns to be glyphs under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Wiring {
+    /// The segment that each wire, `a`, `b`, ..., is connected to.
+    wire_to_segment: Vec<usize>,
+    flips: usize,
+}
+
+/// Every wiring under which the observed patterns are glyphs of the display,
+/// once at most `max_flips` segments in total are flipped.  Observations may
+/// be partial, e.g. fewer than ten digits, and repeated patterns count once.
+///
+/// Every wiring is enumerated, so with few observations this is only
+/// practical for small displays: a single pattern leaves up to `n!` wirings.
+fn consistent_wirings(
+    definition: &DisplayDefinition,
+    patterns: &[&str],
+    max_flips: usize,
+) -> Result<Vec<Wiring>, SolveError> {
+    let mut patterns = wire_masks(definition, patterns)?;
+    patterns.sort_unstable();
+    patterns.dedup();
+    Ok(WiringSolver::new(definition, &patterns, max_flips, usize::MAX).solve())
+}
+
+/// A possible reading of the output patterns.
+#[derive(Clone, Debug, PartialEq)]
+struct Reading {
+    /// The glyphs read, with `?` for a pattern that is equally close to
+    /// different glyphs.
+    value: String,
+    /// The share of the consistent wirings that give this reading, each
+    /// weighted by [`FLIP_LIKELIHOOD`] to the power of its flips.
+    confidence: f64,
+}
+
+/// How much less likely a wiring becomes with each segment it needs flipped,
+/// so that a few wirings that need no flips outweigh many that need some.
+const FLIP_LIKELIHOOD: f64 = 0.01;
+
+/// Reads the output patterns under every wiring consistent with both the
+/// observed patterns and the outputs themselves, allowing at most
+/// `max_flips` flipped segments in total.  Under each wiring, every output is
+/// read as its nearest glyph, and wirings that need fewer flips count for
+/// more.  Readings are ordered by decreasing confidence.
+fn read_outputs(
+    definition: &DisplayDefinition,
+    patterns: &[&str],
+    outputs: &[&str],
+    max_flips: usize,
+) -> Result<Vec<Reading>, SolveError> {
+    let evidence = [patterns, outputs].concat();
+    let wirings = consistent_wirings(definition, &evidence, max_flips)?;
+    if wirings.is_empty() {
+        return Err(SolveError::NoSolution);
+    }
+
+    let mut weights = BTreeMap::new();
+    for wiring in &wirings {
+        let decoder = DisplayDecoder {
+            definition,
+            wire_to_segment: wiring.wire_to_segment.clone(),
+        };
+        let value = outputs
+            .iter()
+            .map(|p| decoder.decode_nearest(p).unwrap_or('?'))
+            .collect::<String>();
+        *weights.entry(value).or_insert(0.0) += FLIP_LIKELIHOOD.powi(wiring.flips as i32);
+    }
+
+    let total = weights.values().sum::<f64>();
+    let mut readings = weights
+        .into_iter()
+        .map(|(value, weight)| Reading {
+            value,
+            confidence: weight / total,
+        })
+        .collect::<Vec<_>>();
+    readings.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+    Ok(readings)
+}
+
+/// A backtracking search for wirings under which each observed pattern is
+/// within a total number of flipped segments of some glyph.
+///
+/// Each pattern can only be a glyph that lights nearly as many segments.
+/// Wires are assigned most constrained first, and a partial assignment is
+/// abandoned as soon as the patterns are too far from every candidate glyph:
+/// wires in a pattern should map into the glyph, and wires outside it should
+/// not, so each assigned wire that does otherwise needs a flip.
+struct WiringSolver<'a> {
+    /// Bitmasks of the wires in each pattern.
+    patterns: &'a [u32],
+    /// The segment bitmasks each pattern could be.
+    candidates: Vec<Vec<u32>>,
+    /// The segments each wire could drive.
+    domains: Vec<u32>,
+    max_flips: usize,
+    /// The search stops after this many solutions.
+    limit: usize,
+    assignment: Vec<Option<usize>>,
+    solutions: Vec<Wiring>,
+}
+
+impl<'a> WiringSolver<'a> {
+    fn new(
+        definition: &DisplayDefinition,
+        patterns: &'a [u32],
+        max_flips: usize,
+        limit: usize,
+    ) -> Self {
+        let n = definition.segments;
+        let candidates = patterns
+            .iter()
+            .map(|p| {
+                definition
+                    .glyphs
+                    .iter()
+                    .map(|(_, mask)| *mask)
+                    .filter(|mask| {
+                        let difference = mask.count_ones() as i64 - p.count_ones() as i64;
+                        difference.unsigned_abs() as usize <= max_flips
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // the segments each wire can possibly drive, considering each pattern
+        // on its own: driving a segment that is lit in none of a pattern's
+        // candidates from a wire in the pattern, or one lit in all of them
+        // from a wire outside it, costs a flip, and a wire can afford at most
+        // `max_flips` of those.
+        let all = (1_u32 << n) - 1;
+        let mut forced_flips = vec![vec![0; n]; n];
+        for (pattern, candidates) in patterns.iter().zip(&candidates) {
+            let inside = candidates.iter().fold(0, |union, mask| union | mask);
+            let outside = candidates
+                .iter()
+                .fold(0, |union, mask| union | (!mask & all));
+            for (wire, flips) in forced_flips.iter_mut().enumerate() {
+                let allowed = if pattern & 1 << wire != 0 {
+                    inside
+                } else {
+                    outside
+                };
+                for (segment, flips) in flips.iter_mut().enumerate() {
+                    if allowed & 1 << segment == 0 {
+                        *flips += 1;
+                    }
+                }
+            }
+        }
+        let domains = forced_flips
+            .iter()
+            .map(|flips| {
+                (0..n)
+                    .filter(|segment| flips[*segment] <= max_flips)
+                    .fold(0, |domain, segment| domain | 1 << segment)
+            })
+            .collect();
+
+        Self {
+            patterns,
+            candidates,
+            domains,
+            max_flips,
+            limit,
+            assignment: vec![None; n],
+            solutions: Vec::new(),
+        }
+    }
+
+    fn solve(mut self) -> Vec<Wiring> {
+        self.search(0);
+        self.solutions
+    }
+
+    /// The segments still available to `wire`.
+    fn options(&self, wire: usize, used: u32) -> u32 {
+        self.domains[wire] & !used
+    }
+
+    fn search(&mut self, used: u32) {
+        if self.solutions.len() >= self.limit {
+            return;
+        }
+
+        // the unassigned wire with the fewest options
+        let next = (0..self.assignment.len())
+            .filter(|w| self.assignment[*w].is_none())
+            .min_by_key(|w| self.options(*w, used).count_ones());
+        let wire = match next {
+            Some(wire) => wire,
+            None => {
+                self.solutions.push(Wiring {
+                    wire_to_segment: self.assignment.iter().map(|s| s.unwrap()).collect(),
+                    flips: self.flips_needed().unwrap(),
+                });
+                return;
+            }
+        };
+
+        let options = self.options(wire, used);
+        for segment in 0..self.assignment.len() {
+            if options & 1 << segment == 0 {
+                continue;
+            }
+            self.assignment[wire] = Some(segment);
+            if self.flips_needed().is_some() {
+                self.search(used | 1 << segment);
+            }
+            self.assignment[wire] = None;
         }
+    }
 
-        i32::from_str(&val_as_string).unwrap()
+    /// The fewest flips that the assigned wires need for every pattern to be
+    /// one of its candidates, or `None` if that is more than allowed.  Once
+    /// every wire is assigned, this is exact.
+    fn flips_needed(&self) -> Option<usize> {
+        let mut total = 0;
+        for (pattern, candidates) in self.patterns.iter().zip(&self.candidates) {
+            let (mut inside, mut outside) = (0_u32, 0_u32);
+            for (wire, segment) in self.assignment.iter().enumerate() {
+                if let Some(segment) = segment {
+                    if pattern & 1 << wire != 0 {
+                        inside |= 1 << segment;
+                    } else {
+                        outside |= 1 << segment;
+                    }
+                }
+            }
+            total += candidates
+                .iter()
+                .map(|mask| ((inside & !mask) | (outside & mask)).count_ones() as usize)
+                .min()?;
+            if total > self.max_flips {
+                return None;
+            }
+        }
+        Some(total)
     }
 }
 
@@ -378,7 +1087,8 @@ impl<T, S: Iterator<Item = T>> AssertUnique<T> for S {
 mod tests {
     use super::*;
 
-    use crate::utils::read_puzzle_input;
+    use crate::utils::{read_puzzle_input, SplitMix64};
+    use std::str::FromStr;
 
     #[test]
     fn solve_part_1() {
@@ -442,4 +1152,445 @@ mod tests {
         assert_eq!(5, decoder.decode("cdfeb"));
         assert_eq!(3, decoder.decode("fcadb"));
     }
+
+    #[test]
+    fn test_intersecting_matches_deduction() {
+        let input = read_puzzle_input("day8-puzzle-input.txt");
+        let input = parse_input(&input);
+        let mut sum = 0;
+        for line in input.iter() {
+            let decoder = build_decoder_by_intersecting(&line.0).unwrap();
+            assert_eq!(decoder, build_decoder(&line.0));
+            sum += decoder.decode_slice(&line.1);
+        }
+        assert_eq!(998900, sum);
+
+        let input = parse_input(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let decoder = build_decoder_by_intersecting(&input[0].0).unwrap();
+        assert_eq!(5353, decoder.decode_slice(&input[0].1));
+    }
+
+    #[test]
+    fn test_intersecting_errors() {
+        let decode = |line: &str| build_decoder_by_intersecting(&parse_input(line)[0].0);
+
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb | ab"),
+            Err(DecodeError::PatternCount(9))
+        );
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb ab ab | ab"),
+            Err(DecodeError::DuplicatePattern)
+        );
+        // the count is checked before duplicates
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab ab | ab"),
+            Err(DecodeError::PatternCount(11))
+        );
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ah | ab"),
+            Err(DecodeError::InvalidWire('h'))
+        );
+        // 0 replaced by a second two-segment pattern
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb ac ab | ab"),
+            Err(DecodeError::AmbiguousLength(2))
+        );
+        // 0 split in two
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb caf edb ab | ab"),
+            Err(DecodeError::PatternCount(11))
+        );
+        // 0 with a segment moved, so that it is no digit at all
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb acdefg ab | ab"),
+            Err(DecodeError::Inconsistent)
+        );
+        assert_eq!(
+            DecodeError::AmbiguousLength(2).to_string(),
+            "expected exactly one pattern with 2 segments"
+        );
+    }
+
+    /// The patterns of the given glyphs on a display whose wire `i` drives
+    /// segment `wiring[i]`.
+    fn scramble(definition: &DisplayDefinition, wiring: &[usize], glyphs: &str) -> Vec<String> {
+        glyphs
+            .chars()
+            .map(|glyph| {
+                let (_, mask) = definition.glyphs.iter().find(|(g, _)| *g == glyph).unwrap();
+                (0..wiring.len())
+                    .filter(|wire| mask & 1 << wiring[*wire] != 0)
+                    .map(|wire| (b'a' + wire as u8) as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_solve_seven_segment() {
+        let definition = DisplayDefinition::seven_segment();
+        let input = read_puzzle_input("day8-puzzle-input.txt");
+        let input = parse_input(&input);
+        let mut sum = 0;
+        for line in input.iter() {
+            let decoder = solve_wiring(&definition, &line.0).unwrap();
+            let output = decoder.decode_all(&line.1).unwrap();
+            let deduced = build_decoder(&line.0);
+            assert_eq!(
+                i32::from_str(&output).unwrap(),
+                deduced.decode_slice(&line.1)
+            );
+            sum += i32::from_str(&output).unwrap();
+
+            // the lookup table agrees with the solver on every possible pattern
+            assert_eq!(deduced.digits.iter().flatten().count(), 10);
+            for mask in 0..128_u8 {
+                let pattern = ('a'..='g')
+                    .filter(|c| mask & 1 << (*c as u8 - b'a') != 0)
+                    .collect::<String>();
+                assert_eq!(
+                    deduced.decode_mask(mask),
+                    decoder
+                        .decode(&pattern)
+                        .map(|d| d.to_digit(10).unwrap() as u8)
+                );
+            }
+            let masks = line
+                .1
+                .iter()
+                .map(|p| pattern_mask(p).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                deduced.decode_masks(&masks),
+                Some(u32::from_str(&output).unwrap())
+            );
+        }
+        assert_eq!(998900, sum);
+    }
+
+    #[test]
+    fn test_solve_alternate_forms() {
+        let definition = DisplayDefinition::seven_segment_with_alternates();
+        let wiring = [3, 6, 0, 5, 1, 4, 2];
+        let observed = scramble(&definition, &wiring, "0123456789");
+        let observed = observed.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let decoder = solve_wiring(&definition, &observed).unwrap();
+        assert_eq!(decoder.wire_to_segment, wiring);
+
+        // the alternate forms decode to the same digits
+        let mut alternate = DisplayDefinition::seven_segment();
+        alternate
+            .glyphs
+            .retain(|(glyph, _)| !"679".contains(*glyph));
+        alternate.glyphs.extend(
+            SEVEN_SEGMENT_ALTERNATES
+                .iter()
+                .map(|(glyph, segments)| (*glyph, segment_mask(segments).unwrap())),
+        );
+        let shown = scramble(&alternate, &wiring, "976");
+        let shown = shown.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        assert_eq!(decoder.decode_all(&shown), Some("976".to_string()));
+    }
+
+    #[test]
+    fn test_solve_fourteen_segment() {
+        let definition = DisplayDefinition::fourteen_segment();
+        let wiring = [9, 2, 13, 0, 7, 11, 4, 1, 12, 5, 10, 3, 8, 6];
+        let glyphs = FOURTEEN_SEGMENT_GLYPHS
+            .iter()
+            .map(|(glyph, _)| *glyph)
+            .collect::<String>();
+        let observed = scramble(&definition, &wiring, &glyphs);
+        let observed = observed.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let decoder = solve_wiring(&definition, &observed).unwrap();
+        assert_eq!(decoder.wire_to_segment, wiring);
+
+        let shown = scramble(&definition, &wiring, "HELLO2021");
+        let shown = shown.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        assert_eq!(decoder.decode_all(&shown), Some("HELLO2021".to_string()));
+    }
+
+    #[test]
+    fn test_solve_errors() {
+        let definition = DisplayDefinition::seven_segment();
+
+        // 1 and 8 alone leave most wires interchangeable
+        assert_eq!(
+            solve_wiring(&definition, &["ab", "abcdefg"]),
+            Err(SolveError::Ambiguous)
+        );
+        assert_eq!(
+            solve_wiring(&definition, &["ab", "cdefgh"]),
+            Err(SolveError::InvalidWire('h'))
+        );
+        // only 1 lights two segments, so two different two-wire patterns
+        // cannot both be digits
+        assert_eq!(
+            solve_wiring(&definition, &["ab", "bc", "acdefg"]),
+            Err(SolveError::NoSolution)
+        );
+        assert_eq!(
+            solve_wiring(&definition, &["aB"]),
+            Err(SolveError::InvalidWire('B'))
+        );
+    }
+
+    #[test]
+    fn test_read_complete_observations() {
+        let definition = DisplayDefinition::seven_segment();
+        let input = read_puzzle_input("day8-puzzle-input.txt");
+        let input = parse_input(&input);
+        for line in input.iter().take(20) {
+            let readings = read_outputs(&definition, &line.0, &line.1, 0).unwrap();
+            assert_eq!(readings.len(), 1);
+            assert_eq!(readings[0].confidence, 1.0);
+            assert_eq!(
+                i32::from_str(&readings[0].value).unwrap(),
+                build_decoder(&line.0).decode_slice(&line.1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_partial_observations() {
+        let definition = DisplayDefinition::seven_segment();
+
+        // 8 alone says nothing about the wiring
+        let wirings = consistent_wirings(&definition, &["acedgfb"], 0).unwrap();
+        assert_eq!(wirings.len(), 5040);
+
+        // 1, 7, 4 and 8 leave the wiring open, but together with the outputs
+        // they are enough to read 5353
+        let patterns = ["ab", "dab", "eafb", "acedgfb"];
+        let outputs = ["cdfeb", "fcadb", "cdfeb", "cdbaf"];
+        assert_eq!(
+            solve_wiring(&definition, &patterns),
+            Err(SolveError::Ambiguous)
+        );
+        let readings = read_outputs(&definition, &patterns, &outputs, 0).unwrap();
+        assert_eq!(readings[0].value, "5353");
+        let total = readings.iter().map(|r| r.confidence).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // only the outputs
+        let readings = read_outputs(&definition, &[], &["ab", "ba", "abc"], 0).unwrap();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].value, "117");
+    }
+
+    #[test]
+    fn test_read_noisy_observations() {
+        let definition = DisplayDefinition::seven_segment();
+        // the example, with one segment of 9 (cefabd) stuck off
+        let patterns = [
+            "acedgfb", "cdfbe", "gcdfa", "fbcad", "dab", "cefab", "cdfgeb", "eafb", "cagedb", "ab",
+        ];
+        let outputs = ["cdfeb", "fcadb", "cdfeb", "cdbaf"];
+        assert_eq!(
+            read_outputs(&definition, &patterns, &outputs, 0),
+            Err(SolveError::NoSolution)
+        );
+
+        let wirings = consistent_wirings(&definition, &patterns, 1).unwrap();
+        let exact = build_decoder_by_intersecting(&[
+            "acedgfb", "cdfbe", "gcdfa", "fbcad", "dab", "cefabd", "cdfgeb", "eafb", "cagedb", "ab",
+        ])
+        .unwrap();
+        assert!(wirings.iter().all(|w| w.flips == 1));
+        let expected = ('a'..='g')
+            .map(|c| (exact.map[&c] as u8 - b'a') as usize)
+            .collect::<Vec<_>>();
+        assert!(wirings.iter().any(|w| w.wire_to_segment == expected));
+
+        let readings = read_outputs(&definition, &patterns, &outputs, 1).unwrap();
+        assert_eq!(readings[0].value, "5353");
+
+        // 5 (cdfeb) with a segment stuck off is still read as 5
+        let readings = read_outputs(&definition, &patterns, &["cdfe", "ab"], 2).unwrap();
+        assert_eq!(
+            readings,
+            vec![Reading {
+                value: "51".to_string(),
+                confidence: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_clean_reading_beats_noisy_ones() {
+        let definition = DisplayDefinition::seven_segment();
+        let patterns = ["ab", "dab"];
+        let outputs = ["ab", "abd"];
+
+        // 17 needs no flips, but each of 11, 77 and 1? has twice as many
+        // wirings, all of which need a flip
+        let wirings =
+            consistent_wirings(&definition, &[&patterns[..], &outputs[..]].concat(), 1).unwrap();
+        let clean = wirings.iter().filter(|w| w.flips == 0).count();
+        assert_eq!(clean, 48);
+        assert_eq!(wirings.len() - clean, 288);
+
+        let readings = read_outputs(&definition, &patterns, &outputs, 1).unwrap();
+        assert_eq!(readings.len(), 4);
+        assert_eq!(readings[0].value, "17");
+        let expected = 48.0 / (48.0 + 288.0 * FLIP_LIKELIHOOD);
+        assert!((readings[0].confidence - expected).abs() < 1e-9);
+        assert!(readings[1..]
+            .iter()
+            .all(|r| r.confidence < readings[0].confidence / 10.0));
+    }
+
+    /// Every glyph of the 14-segment display, one with a segment stuck off,
+    /// is still enough to read the outputs.
+    #[test]
+    fn test_read_noisy_fourteen_segment() {
+        let definition = DisplayDefinition::fourteen_segment();
+        let wiring = [9, 2, 13, 0, 7, 11, 4, 1, 12, 5, 10, 3, 8, 6];
+        let glyphs = FOURTEEN_SEGMENT_GLYPHS
+            .iter()
+            .map(|(glyph, _)| *glyph)
+            .collect::<String>();
+        let mut observed = scramble(&definition, &wiring, &glyphs);
+        // one segment of the first glyph stuck off
+        observed[0].pop();
+        let observed = observed.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let shown = scramble(&definition, &wiring, "HELLO2021");
+        let shown = shown.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+
+        let readings = read_outputs(&definition, &observed, &shown, 1).unwrap();
+        assert_eq!(readings[0].value, "HELLO2021");
+    }
+
+    #[test]
+    fn test_pattern_mask() {
+        assert_eq!(pattern_mask(""), Some(0));
+        assert_eq!(pattern_mask("ab"), Some(0b11));
+        assert_eq!(pattern_mask("gfedcba"), Some(0x7f));
+        assert_eq!(pattern_mask("ah"), None);
+
+        let decoder = build_decoder(&[
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+        ]);
+        assert_eq!(decoder.try_decode("cf"), Some(1));
+        assert_eq!(decoder.try_decode("cx"), None);
+        assert_eq!(decoder.try_decode("c"), None);
+        assert_eq!(decoder.decode_masks(&[0b0100100, 0b1111111]), Some(18));
+        assert_eq!(decoder.decode_masks(&[0b0100100, 0b0000001]), None);
+        // the eighth bit is not a wire, rather than an alias for the others
+        assert_eq!(decoder.decode_mask(0b0100100), Some(1));
+        assert_eq!(decoder.decode_mask(0b1010_0100), None);
+    }
+
+    /// A random wiring.
+    fn random_decoder(rng: &mut SplitMix64) -> SevenSegmentDisplayEncoding {
+        let mut wires = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+        rng.shuffle(&mut wires);
+        let [a, b, c, d, e, f, g] = wires;
+        SevenSegmentDisplayEncoding::new(a, b, c, d, e, f, g)
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let mut rng = SplitMix64::new(2021);
+        for n in 0..200 {
+            let wiring = random_decoder(&mut rng);
+
+            // the ten digits, in a scrambled order
+            let mut patterns = (0..10)
+                .map(|d| wiring.encode_digit(d).unwrap())
+                .collect::<Vec<_>>();
+            patterns.rotate_left(n % 10);
+            patterns.swap(0, 9 - n % 7);
+            let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+
+            assert_eq!(build_decoder(&patterns), wiring);
+            assert_eq!(build_decoder_by_intersecting(&patterns), Ok(wiring));
+
+            let decoder = build_decoder(&patterns);
+            let number = (n as u32 * 7919) % 10_000;
+            let output = decoder.encode(number, 4).unwrap();
+            let output = output.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+            assert_eq!(decoder.decode_slice(&output), number as i32);
+        }
+    }
+
+    #[test]
+    fn test_encode_example() {
+        let input = parse_input(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let decoder = build_decoder(&input[0].0);
+        assert_eq!(
+            decoder.encode(5353, 4).unwrap(),
+            vec!["bcdef", "abcdf", "bcdef", "abcdf"]
+        );
+        assert_eq!(
+            decoder.encode(17, 4).unwrap(),
+            vec!["abcdeg", "abcdeg", "ab", "abd"]
+        );
+        assert_eq!(decoder.encode_digit(8).unwrap(), "abcdefg");
+        assert_eq!(decoder.encode_digit(10), None);
+        assert_eq!(decoder.wire_for('c'), Some('a'));
+        assert_eq!(decoder.wire_for('h'), None);
+    }
+
+    #[test]
+    fn test_encode_too_wide() {
+        assert_eq!(
+            random_decoder(&mut SplitMix64::new(1)).encode(12345, 4),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let identity = SevenSegmentDisplayEncoding::new('a', 'b', 'c', 'd', 'e', 'f', 'g');
+        let patterns = identity.encode(1234, 5).unwrap();
+        let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            identity.render(&patterns, SegmentLabels::Real).unwrap(),
+            " aaaa    ....    aaaa    aaaa    ....
+b    c  .    c  .    c  .    c  b    c
+b    c  .    c  .    c  .    c  b    c
+ ....    ....    dddd    dddd    dddd
+e    f  .    f  e    .  .    f  .    f
+e    f  .    f  e    .  .    f  .    f
+ gggg    ....    gggg    gggg    ....
+"
+        );
+
+        // the wiring of the example, labelled by wire
+        let input = parse_input(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let decoder = build_decoder(&input[0].0);
+        assert_eq!(
+            decoder
+                .render(&["acedgfb"], SegmentLabels::Scrambled)
+                .unwrap(),
+            " dddd
+e    a
+e    a
+ ffff
+g    b
+g    b
+ cccc
+"
+        );
+        assert_eq!(
+            decoder.render(&["ab"], SegmentLabels::Real).unwrap(),
+            " ....
+.    c
+.    c
+ ....
+.    f
+.    f
+ ....
+"
+        );
+        assert_eq!(decoder.render(&["ab", "ax"], SegmentLabels::Real), None);
+    }
 }